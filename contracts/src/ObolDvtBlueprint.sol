@@ -8,6 +8,23 @@ import "tnt-core/BlueprintServiceManager.sol";
  * @dev This contract is an example of a service blueprint that provides a single service.
  */
 contract ObolDvtBlueprint is BlueprintServiceManager {
+    /**
+     * @dev The parameters of the distributed validator cluster requested for a service.
+     * @param name The name of the cluster.
+     * @param validatorCount The number of distributed validators to create.
+     * @param feeRecipients The fee recipient address of each validator.
+     * @param withdrawalAddresses The withdrawal address of each validator.
+     */
+    struct DkgParams {
+        string name;
+        uint32 validatorCount;
+        string[] feeRecipients;
+        string[] withdrawalAddresses;
+    }
+
+    /// @dev The requested cluster parameters, by service ID.
+    mapping(uint64 => DkgParams) private dkgParams;
+
     /**
      * @dev Hook for service operator registration. Called when a service operator
      * attempts to register with the blueprint.
@@ -28,15 +45,47 @@ contract ObolDvtBlueprint is BlueprintServiceManager {
      * instance from the blueprint.
      * @param serviceId The ID of the requested service.
      * @param operators The operators involved in the service.
-     * @param _requestInputs Inputs required for the service request.
+     * @param requestInputs The ABI encoded cluster parameters, in the order of the blueprint's
+     * request params: `(string name, string[] feeRecipients, uint32 validatorCount, string[] withdrawalAddresses)`.
      */
-    function onRequest(uint64 serviceId, bytes[] calldata operators, bytes calldata _requestInputs)
+    function onRequest(uint64 serviceId, bytes[] calldata operators, bytes calldata requestInputs)
     public
     payable
     override
     onlyFromRootChain
     {
-        // Do something with the service request
+        (string memory name, string[] memory feeRecipients, uint32 validatorCount, string[] memory withdrawalAddresses) =
+            abi.decode(requestInputs, (string, string[], uint32, string[]));
+
+        require(bytes(name).length > 0, "Cluster name must not be empty");
+        require(validatorCount > 0, "At least one validator is required");
+        require(feeRecipients.length == validatorCount, "Expected one fee recipient per validator");
+        require(withdrawalAddresses.length == validatorCount, "Expected one withdrawal address per validator");
+        for (uint256 i = 0; i < validatorCount; i++) {
+            require(isHexAddress(feeRecipients[i]), "Fee recipients must be 0x prefixed hex addresses");
+            require(isHexAddress(withdrawalAddresses[i]), "Withdrawal addresses must be 0x prefixed hex addresses");
+        }
+
+        dkgParams[serviceId] = DkgParams(name, validatorCount, feeRecipients, withdrawalAddresses);
+    }
+
+    /**
+     * @dev Returns the cluster parameters a service was requested with.
+     * @param serviceId The ID of the service.
+     */
+    function getDkgParams(uint64 serviceId)
+    external
+    view
+    returns (
+        string memory name,
+        uint32 validatorCount,
+        string[] memory feeRecipients,
+        string[] memory withdrawalAddresses
+    )
+    {
+        DkgParams storage params = dkgParams[serviceId];
+        require(params.validatorCount > 0, "Unknown service");
+        return (params.name, params.validatorCount, params.feeRecipients, params.withdrawalAddresses);
     }
 
     /**
@@ -84,6 +133,27 @@ contract ObolDvtBlueprint is BlueprintServiceManager {
         return true;
     }
 
+    /**
+     * @dev Checks that a string is a `0x` prefixed, hex encoded 20 byte address, as charon expects.
+     * @param value The string to check.
+     * @return bool Whether the string is such an address.
+     */
+    function isHexAddress(string memory value) internal pure returns (bool) {
+        bytes memory chars = bytes(value);
+        if (chars.length != 42 || chars[0] != "0" || chars[1] != "x") {
+            return false;
+        }
+
+        for (uint256 i = 2; i < chars.length; i++) {
+            bytes1 c = chars[i];
+            if (!((c >= "0" && c <= "9") || (c >= "a" && c <= "f") || (c >= "A" && c <= "F"))) {
+                return false;
+            }
+        }
+
+        return true;
+    }
+
     /**
      * @dev Converts a public key to an operator address.
      * @param publicKey The public key to convert.
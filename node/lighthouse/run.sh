@@ -0,0 +1,31 @@
+#!/bin/sh
+
+for f in /opt/charon/keys/keystore-*.json; do
+  echo "Importing key ${f}"
+
+  # Import the keystore with its password
+  lighthouse --network "${NETWORK}" account validator import \
+    --datadir /opt/data \
+    --reuse-password \
+    --keystore "${f}" \
+    --password-file "${f%.json}.txt"
+done
+
+# Propose blocks built by MEV-boost's relays, through charon's builder API
+builder_args=""
+if [ "${BUILDER_API_ENABLED}" = "true" ]; then
+  builder_args="--builder-proposals"
+fi
+
+echo "Starting lighthouse validator client"
+exec lighthouse --network "${NETWORK}" validator \
+  --datadir /opt/data \
+  --beacon-nodes "${BEACON_NODE_ADDRESS}" \
+  --suggested-fee-recipient "0x0000000000000000000000000000000000000000" \
+  --metrics \
+  --metrics-address "0.0.0.0" \
+  --metrics-allow-origin "*" \
+  --metrics-port "5064" \
+  --use-long-timeouts \
+  --distributed \
+  ${builder_args}
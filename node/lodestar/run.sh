@@ -0,0 +1,28 @@
+#!/bin/sh
+
+for f in /opt/charon/keys/keystore-*.json; do
+  echo "Importing key ${f}"
+
+  node /usr/app/packages/cli/bin/lodestar validator import \
+    --dataDir=/opt/data \
+    --network="${NETWORK}" \
+    --importKeystores="${f}" \
+    --importKeystoresPassword="${f%.json}.txt"
+done
+
+# Propose blocks built by MEV-boost's relays, through charon's builder API
+builder_args=""
+if [ "${BUILDER_API_ENABLED}" = "true" ]; then
+  builder_args="--builder"
+fi
+
+echo "Starting lodestar validator client"
+exec node /usr/app/packages/cli/bin/lodestar validator \
+  --dataDir=/opt/data \
+  --network="${NETWORK}" \
+  --beaconNodes="${BEACON_NODE_ADDRESS}" \
+  --metrics=true \
+  --metrics.address="0.0.0.0" \
+  --metrics.port="5064" \
+  --distributed \
+  ${builder_args}
@@ -0,0 +1,32 @@
+#!/bin/sh
+
+# Nimbus expects each keystore in validators/<pubkey>/keystore.json, and its password in secrets/<pubkey>
+mkdir -p /opt/data/validators /opt/data/secrets
+chmod 700 /opt/data/validators /opt/data/secrets
+
+for f in /opt/charon/keys/keystore-*.json; do
+  pubkey="0x$(sed -n 's/.*"pubkey": *"\(0x\)\{0,1\}\([0-9a-fA-F]*\)".*/\2/p' "${f}")"
+  echo "Importing key ${pubkey}"
+
+  mkdir -p "/opt/data/validators/${pubkey}"
+  cp "${f}" "/opt/data/validators/${pubkey}/keystore.json"
+  cp "${f%.json}.txt" "/opt/data/secrets/${pubkey}"
+  chmod 600 "/opt/data/validators/${pubkey}/keystore.json" "/opt/data/secrets/${pubkey}"
+done
+
+# Propose blocks built by MEV-boost's relays, through charon's builder API
+builder_args=""
+if [ "${BUILDER_API_ENABLED}" = "true" ]; then
+  builder_args="--payload-builder=true"
+fi
+
+echo "Starting nimbus validator client"
+exec /home/user/nimbus_validator_client \
+  --data-dir=/opt/data \
+  --beacon-node="${BEACON_NODE_ADDRESS}" \
+  --doppelganger-detection=false \
+  --metrics \
+  --metrics-address="0.0.0.0" \
+  --metrics-port="5064" \
+  --distributed \
+  ${builder_args}
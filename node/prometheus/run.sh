@@ -0,0 +1,12 @@
+#!/bin/sh
+
+if [ -z "$PROM_REMOTE_WRITE_TOKEN" ]; then
+  echo "\$PROM_REMOTE_WRITE_TOKEN is empty, not pushing metrics"
+  sed '/^remote_write:/,/^$/d' /etc/prometheus/prometheus.yml.example > /tmp/prometheus.yml
+else
+  sed "s|\$PROM_REMOTE_WRITE_TOKEN|${PROM_REMOTE_WRITE_TOKEN}|g" /etc/prometheus/prometheus.yml.example > /tmp/prometheus.yml
+fi
+
+exec /bin/prometheus \
+  --config.file=/tmp/prometheus.yml \
+  --storage.tsdb.path=/prometheus
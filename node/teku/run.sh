@@ -0,0 +1,23 @@
+#!/bin/sh
+
+# Teku loads the keystores in place, with the password in the matching keystore-*.txt
+# Propose blocks built by MEV-boost's relays, through charon's builder API
+builder_args=""
+if [ "${BUILDER_API_ENABLED}" = "true" ]; then
+  builder_args="--validators-builder-registration-default-enabled=true"
+fi
+
+echo "Starting teku validator client"
+exec /opt/teku/bin/teku validator-client \
+  --network="${NETWORK}" \
+  --data-path=/opt/data \
+  --beacon-node-api-endpoint="${BEACON_NODE_ADDRESS}" \
+  --validator-keys=/opt/charon/keys:/opt/charon/keys \
+  --validators-keystore-locking-enabled=false \
+  --validators-proposer-default-fee-recipient="0x0000000000000000000000000000000000000000" \
+  --metrics-enabled=true \
+  --metrics-host-allowlist="*" \
+  --metrics-interface="0.0.0.0" \
+  --metrics-port="5064" \
+  --Xobol-dvt-integration-enabled=true \
+  ${builder_args}
@@ -0,0 +1,698 @@
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use color_eyre::eyre::{bail, eyre};
+use color_eyre::Result;
+use gadget_sdk as sdk;
+use sdk::ext::sp_core::ecdsa;
+use sdk::ext::sp_core::hashing::blake2_256;
+use sdk::random::rand::rngs::OsRng;
+use sdk::random::RngCore;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Component, Path, PathBuf};
+use x25519_dalek::{PublicKey, SharedSecret, StaticSecret};
+
+/// Identifies an encrypted archive, and the version of its format
+const MAGIC: &[u8; 8] = b"OBOLARC1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const BLOCK: usize = 512;
+
+/// Domain separation for the keys derived from the operator's ECDSA key
+const OPERATOR_SECRET_CONTEXT: &[u8] = b"obol-dvt-blueprint/archive-operator-secret";
+/// Domain separation for the keys archives are encrypted under
+const SECRET_KEY_CONTEXT: &[u8] = b"obol-dvt-blueprint/archive-secret-key";
+const RECIPIENT_KEY_CONTEXT: &[u8] = b"obol-dvt-blueprint/archive-recipient-key";
+
+/// What an archive is encrypted to, recorded in its header
+#[derive(Clone)]
+pub enum ArchiveKey {
+    /// A passphrase, stretched with scrypt
+    Passphrase(String),
+    /// A secret derived from the operator's ECDSA key, so the operator can decrypt it on any host
+    Operator([u8; 32]),
+    /// An X25519 public key, only its secret key can decrypt the archive
+    Recipient(PublicKey),
+}
+
+impl ArchiveKey {
+    const PASSPHRASE: u8 = 0;
+    const OPERATOR: u8 = 1;
+    const RECIPIENT: u8 = 2;
+}
+
+/// The keys an operator encrypts archives to and decrypts them with
+#[derive(Clone)]
+pub struct ArchiveKeys {
+    /// `OBOL_ARCHIVE_PASSPHRASE`
+    passphrase: Option<String>,
+    operator_secret: [u8; 32],
+    /// `OBOL_BACKUP_RECIPIENT`, a hex X25519 public key
+    recipient: Option<PublicKey>,
+    /// `OBOL_BACKUP_IDENTITY`, the hex X25519 secret key of a recipient
+    identity: Option<StaticSecret>,
+}
+
+impl ArchiveKeys {
+    pub fn from_env(operator_key: &ecdsa::Pair) -> Result<Self> {
+        fn var(name: &str) -> Option<String> {
+            std::env::var(name)
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        }
+
+        fn x25519_key(name: &str) -> Result<Option<[u8; 32]>> {
+            let Some(value) = var(name) else {
+                return Ok(None);
+            };
+
+            let bytes = hex::decode(value.strip_prefix("0x").unwrap_or(&value))
+                .map_err(|e| eyre!("Invalid {name}: {e}"))?;
+            let key = <[u8; 32]>::try_from(bytes)
+                .map_err(|_| eyre!("Invalid {name}, expected 32 hex encoded bytes"))?;
+            Ok(Some(key))
+        }
+
+        Ok(Self {
+            passphrase: var("OBOL_ARCHIVE_PASSPHRASE"),
+            operator_secret: blake2_256(&[OPERATOR_SECRET_CONTEXT, &operator_key.seed()].concat()),
+            recipient: x25519_key("OBOL_BACKUP_RECIPIENT")?.map(PublicKey::from),
+            identity: x25519_key("OBOL_BACKUP_IDENTITY")?.map(StaticSecret::from),
+        })
+    }
+
+    /// The key teardown archives are encrypted to, `OBOL_ARCHIVE_PASSPHRASE`
+    pub fn passphrase(&self) -> Result<ArchiveKey> {
+        self.passphrase
+            .clone()
+            .map(ArchiveKey::Passphrase)
+            .ok_or_else(|| eyre!("OBOL_ARCHIVE_PASSPHRASE must be set to encrypt archives"))
+    }
+
+    /// The key backups are encrypted to, the recipient if there is one, otherwise the operator's
+    pub fn backup(&self) -> ArchiveKey {
+        match self.recipient {
+            Some(recipient) => ArchiveKey::Recipient(recipient),
+            None => ArchiveKey::Operator(self.operator_secret),
+        }
+    }
+
+    /// Whether there's a key to decrypt `archive` with, failing if it isn't a valid archive
+    ///
+    /// Only the archive's header is checked, it isn't decrypted.
+    pub fn can_decrypt(&self, archive: &[u8]) -> Result<bool> {
+        let sealed = Sealed::parse(archive)?;
+        Ok(match sealed.kind {
+            ArchiveKey::PASSPHRASE => self.passphrase.is_some(),
+            ArchiveKey::RECIPIENT => self.identity.is_some(),
+            _ => true,
+        })
+    }
+
+    /// The key to decrypt an archive encrypted to a key of `kind`, with `header` from the archive
+    fn decryption_key(&self, kind: u8, header: &[u8]) -> Result<Key> {
+        match kind {
+            ArchiveKey::PASSPHRASE => {
+                let passphrase = self.passphrase.as_deref().ok_or_else(|| {
+                    eyre!("The archive is encrypted with a passphrase, set OBOL_ARCHIVE_PASSPHRASE")
+                })?;
+                passphrase_key(passphrase, header)
+            }
+            ArchiveKey::OPERATOR => Ok(secret_key(&self.operator_secret, header)),
+            ArchiveKey::RECIPIENT => {
+                let identity = self.identity.as_ref().ok_or_else(|| {
+                    eyre!("The archive is encrypted to an X25519 recipient, set OBOL_BACKUP_IDENTITY to its secret key")
+                })?;
+                let ephemeral = PublicKey::from(<[u8; 32]>::try_from(header)?);
+                recipient_key(
+                    identity.diffie_hellman(&ephemeral),
+                    &ephemeral,
+                    &PublicKey::from(identity),
+                )
+            }
+            _ => bail!("Unsupported archive key type {kind}"),
+        }
+    }
+}
+
+/// The archive a fresh host restores its node from, `OBOL_RESTORE_ARCHIVE`
+pub fn restore_archive_path() -> Option<PathBuf> {
+    std::env::var_os("OBOL_RESTORE_ARCHIVE")
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Pack `paths`, relative to `root`, into a tarball encrypted to `key`
+///
+/// Paths that don't exist are skipped. The archive is encrypted with XChaCha20-Poly1305.
+pub fn create_archive(root: &Path, paths: &[&str], key: &ArchiveKey) -> Result<Vec<u8>> {
+    let mut tar = Vec::new();
+    for path in paths {
+        if root.join(path).exists() {
+            append_path(&mut tar, root, Path::new(path))?;
+        }
+    }
+    tar.extend_from_slice(&[0; 2 * BLOCK]);
+
+    encrypt(&tar, key)
+}
+
+/// Decrypt an archive and unpack it into `dest`, returning the files that were written
+pub fn extract_archive(archive: &[u8], keys: &ArchiveKeys, dest: &Path) -> Result<Vec<PathBuf>> {
+    let tar = decrypt(archive, keys)?;
+
+    let mut written = Vec::new();
+    for entry in read_entries(&tar)? {
+        let path = dest.join(&entry.path);
+        if entry.is_dir {
+            std::fs::create_dir_all(&path)?;
+        } else {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&path, entry.content)?;
+            written.push(path.clone());
+        }
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(entry.mode))?;
+    }
+
+    Ok(written)
+}
+
+/// Decrypt an archive and list the files in it, without unpacking it
+pub fn list_archive(archive: &[u8], keys: &ArchiveKeys) -> Result<Vec<PathBuf>> {
+    let tar = decrypt(archive, keys)?;
+    Ok(read_entries(&tar)?
+        .into_iter()
+        .filter(|entry| !entry.is_dir)
+        .map(|entry| entry.path)
+        .collect())
+}
+
+fn passphrase_key(passphrase: &str, salt: &[u8]) -> Result<Key> {
+    let params = scrypt::Params::new(15, 8, 1, 32).map_err(|e| eyre!("{e}"))?;
+    let mut key = Key::default();
+    scrypt::scrypt(passphrase.as_bytes(), salt, &params, &mut key).map_err(|e| eyre!("{e}"))?;
+    Ok(key)
+}
+
+fn secret_key(secret: &[u8; 32], salt: &[u8]) -> Key {
+    Key::from(blake2_256(&[SECRET_KEY_CONTEXT, secret, salt].concat()))
+}
+
+fn recipient_key(
+    shared: SharedSecret,
+    ephemeral: &PublicKey,
+    recipient: &PublicKey,
+) -> Result<Key> {
+    if !shared.was_contributory() {
+        bail!("Invalid X25519 key for the archive");
+    }
+
+    Ok(Key::from(blake2_256(
+        &[
+            RECIPIENT_KEY_CONTEXT,
+            shared.as_bytes(),
+            ephemeral.as_bytes(),
+            recipient.as_bytes(),
+        ]
+        .concat(),
+    )))
+}
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0u8; N];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+/// `MAGIC | key type | salt or ephemeral X25519 key | nonce | ciphertext`
+fn encrypt(plaintext: &[u8], key: &ArchiveKey) -> Result<Vec<u8>> {
+    let (kind, header, cipher_key) = match key {
+        ArchiveKey::Passphrase(passphrase) => {
+            let salt = random_bytes::<SALT_LEN>();
+            let key = passphrase_key(passphrase, &salt)?;
+            (ArchiveKey::PASSPHRASE, salt.to_vec(), key)
+        }
+        ArchiveKey::Operator(secret) => {
+            let salt = random_bytes::<SALT_LEN>();
+            let key = secret_key(secret, &salt);
+            (ArchiveKey::OPERATOR, salt.to_vec(), key)
+        }
+        ArchiveKey::Recipient(recipient) => {
+            let ephemeral = StaticSecret::from(random_bytes::<32>());
+            let ephemeral_public = PublicKey::from(&ephemeral);
+            let key = recipient_key(
+                ephemeral.diffie_hellman(recipient),
+                &ephemeral_public,
+                recipient,
+            )?;
+            (
+                ArchiveKey::RECIPIENT,
+                ephemeral_public.as_bytes().to_vec(),
+                key,
+            )
+        }
+    };
+
+    let nonce = XNonce::from(random_bytes::<NONCE_LEN>());
+    let ciphertext = XChaCha20Poly1305::new(&cipher_key)
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| eyre!("Failed to encrypt archive"))?;
+
+    let mut archive = MAGIC.to_vec();
+    archive.push(kind);
+    archive.extend_from_slice(&header);
+    archive.extend_from_slice(&nonce);
+    archive.extend_from_slice(&ciphertext);
+    Ok(archive)
+}
+
+/// The parts of an encrypted archive, see [`encrypt`]
+struct Sealed<'a> {
+    /// The type of [`ArchiveKey`] it's encrypted to
+    kind: u8,
+    /// The salt, or the ephemeral X25519 key
+    header: &'a [u8],
+    nonce: &'a [u8],
+    ciphertext: &'a [u8],
+}
+
+impl<'a> Sealed<'a> {
+    /// Split an archive into its parts, without decrypting it
+    fn parse(archive: &'a [u8]) -> Result<Self> {
+        let Some(rest) = archive.strip_prefix(MAGIC.as_slice()) else {
+            bail!("Not an encrypted archive");
+        };
+        let Some((&kind, rest)) = rest.split_first() else {
+            bail!("Archive is truncated");
+        };
+
+        let header_len = match kind {
+            ArchiveKey::PASSPHRASE | ArchiveKey::OPERATOR => SALT_LEN,
+            ArchiveKey::RECIPIENT => 32,
+            _ => bail!("Unsupported archive key type {kind}"),
+        };
+        if rest.len() < header_len + NONCE_LEN {
+            bail!("Archive is truncated");
+        }
+
+        let (header, rest) = rest.split_at(header_len);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        Ok(Self {
+            kind,
+            header,
+            nonce,
+            ciphertext,
+        })
+    }
+}
+
+fn decrypt(archive: &[u8], keys: &ArchiveKeys) -> Result<Vec<u8>> {
+    let sealed = Sealed::parse(archive)?;
+    let cipher_key = keys.decryption_key(sealed.kind, sealed.header)?;
+    XChaCha20Poly1305::new(&cipher_key)
+        .decrypt(XNonce::from_slice(sealed.nonce), sealed.ciphertext)
+        .map_err(|_| eyre!("Failed to decrypt archive, wrong key or corrupted archive"))
+}
+
+fn append_path(tar: &mut Vec<u8>, root: &Path, path: &Path) -> Result<()> {
+    let full_path = root.join(path);
+    let metadata = std::fs::symlink_metadata(&full_path)?;
+    let mode = metadata.permissions().mode() & 0o7777;
+
+    if metadata.is_dir() {
+        tar.extend_from_slice(&header(path, mode, 0, b'5')?);
+
+        let mut entries = std::fs::read_dir(&full_path)?
+            .map(|entry| entry.map(|entry| entry.file_name()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort();
+        for name in entries {
+            append_path(tar, root, &path.join(name))?;
+        }
+    } else if metadata.is_file() {
+        let content = std::fs::read(&full_path)?;
+        tar.extend_from_slice(&header(path, mode, content.len() as u64, b'0')?);
+        tar.extend_from_slice(&content);
+        tar.resize(tar.len().next_multiple_of(BLOCK), 0);
+    } else {
+        tracing::warn!("Not archiving {}, not a regular file", full_path.display());
+    }
+
+    Ok(())
+}
+
+/// A ustar header
+fn header(path: &Path, mode: u32, size: u64, kind: u8) -> Result<[u8; BLOCK]> {
+    let path = path
+        .to_str()
+        .ok_or_else(|| eyre!("Path {} is not UTF-8", path.display()))?;
+
+    // Long paths are split into a prefix and a name at a `/`
+    let (prefix, name) = if path.len() <= 100 {
+        ("", path)
+    } else {
+        let split = path
+            .char_indices()
+            .filter(|(i, c)| *c == '/' && *i <= 155 && path.len() - i - 1 <= 100)
+            .map(|(i, _)| i)
+            .next()
+            .ok_or_else(|| eyre!("Path {path} is too long to archive"))?;
+        (&path[..split], &path[split + 1..])
+    };
+
+    let mut header = [0u8; BLOCK];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    write_octal(&mut header[100..108], u64::from(mode));
+    write_octal(&mut header[108..116], 0);
+    write_octal(&mut header[116..124], 0);
+    write_octal(&mut header[124..136], size);
+    write_octal(&mut header[136..148], 0);
+    header[156] = kind;
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+
+    header[148..156].fill(b' ');
+    let checksum = header.iter().map(|b| u64::from(*b)).sum::<u64>();
+    write_octal(&mut header[148..155], checksum);
+
+    Ok(header)
+}
+
+/// Write `value` as zero padded octal digits followed by a NUL
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = format!("{value:0width$o}", width = field.len() - 1);
+    field[..digits.len()].copy_from_slice(digits.as_bytes());
+    field[digits.len()] = 0;
+}
+
+struct Entry {
+    path: PathBuf,
+    mode: u32,
+    is_dir: bool,
+    content: Vec<u8>,
+}
+
+fn read_entries(tar: &[u8]) -> Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    while offset + BLOCK <= tar.len() {
+        let header = &tar[offset..offset + BLOCK];
+        if header.iter().all(|b| *b == 0) {
+            return Ok(entries);
+        }
+
+        let name = read_str(&header[..100])?;
+        let prefix = read_str(&header[345..500])?;
+        let path = if prefix.is_empty() {
+            PathBuf::from(name)
+        } else {
+            Path::new(prefix).join(name)
+        };
+        if !path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            bail!("Archive contains an unsafe path {}", path.display());
+        }
+
+        let mode = read_octal(&header[100..108])? as u32;
+        let size = read_octal(&header[124..136])? as usize;
+        let is_dir = header[156] == b'5';
+
+        offset += BLOCK;
+        let Some(content) = tar.get(offset..offset + size) else {
+            bail!("Archive is truncated");
+        };
+        offset += size.next_multiple_of(BLOCK);
+
+        entries.push(Entry {
+            path,
+            mode,
+            is_dir,
+            content: content.to_vec(),
+        });
+    }
+
+    bail!("Archive is truncated")
+}
+
+fn read_str(field: &[u8]) -> Result<&str> {
+    let end = field.iter().position(|b| *b == 0).unwrap_or(field.len());
+    Ok(std::str::from_utf8(&field[..end])?)
+}
+
+fn read_octal(field: &[u8]) -> Result<u64> {
+    let digits = read_str(field)?.trim();
+    u64::from_str_radix(digits, 8).map_err(|e| eyre!("Invalid archive header: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys() -> ArchiveKeys {
+        ArchiveKeys {
+            passphrase: Some(String::from("correct horse battery staple")),
+            operator_secret: [7; 32],
+            recipient: None,
+            identity: None,
+        }
+    }
+
+    fn write(root: &Path, path: &str, content: &[u8], mode: u32) {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, content).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    fn mode(path: &Path) -> u32 {
+        std::fs::metadata(path).unwrap().permissions().mode() & 0o7777
+    }
+
+    #[test]
+    fn round_trips_nested_dirs_and_modes() {
+        let src = tempfile::tempdir().unwrap();
+        write(
+            src.path(),
+            ".charon/charon-enr-private-key",
+            b"enr key",
+            0o600,
+        );
+        write(
+            src.path(),
+            ".charon/validator_keys/keystore-0.json",
+            b"{}",
+            0o644,
+        );
+        write(
+            src.path(),
+            ".charon/validator_keys/keystore-0.txt",
+            b"pw",
+            0o400,
+        );
+        std::fs::set_permissions(
+            src.path().join(".charon/validator_keys"),
+            std::fs::Permissions::from_mode(0o700),
+        )
+        .unwrap();
+
+        let keys = keys();
+        let archive = create_archive(
+            src.path(),
+            &[".charon", "missing"],
+            &ArchiveKey::Operator(keys.operator_secret),
+        )
+        .unwrap();
+        assert!(archive.starts_with(MAGIC));
+
+        let mut listed = list_archive(&archive, &keys).unwrap();
+        listed.sort();
+        assert_eq!(
+            listed,
+            [
+                ".charon/charon-enr-private-key",
+                ".charon/validator_keys/keystore-0.json",
+                ".charon/validator_keys/keystore-0.txt",
+            ]
+            .map(PathBuf::from)
+        );
+
+        let dest = tempfile::tempdir().unwrap();
+        let written = extract_archive(&archive, &keys, dest.path()).unwrap();
+        assert_eq!(written.len(), 3);
+
+        let keys_dir = dest.path().join(".charon/validator_keys");
+        assert_eq!(
+            std::fs::read(dest.path().join(".charon/charon-enr-private-key")).unwrap(),
+            b"enr key"
+        );
+        assert_eq!(
+            std::fs::read(keys_dir.join("keystore-0.txt")).unwrap(),
+            b"pw"
+        );
+        assert_eq!(
+            mode(&dest.path().join(".charon/charon-enr-private-key")),
+            0o600
+        );
+        assert_eq!(mode(&keys_dir.join("keystore-0.json")), 0o644);
+        assert_eq!(mode(&keys_dir.join("keystore-0.txt")), 0o400);
+        assert_eq!(mode(&keys_dir), 0o700);
+    }
+
+    #[test]
+    fn splits_long_paths_into_a_prefix() {
+        let src = tempfile::tempdir().unwrap();
+        let long_path = format!("data/{}/{}.json", "d".repeat(80), "f".repeat(60));
+        assert!(long_path.len() > 100);
+        write(src.path(), &long_path, b"long", 0o644);
+
+        let header = header(Path::new(&long_path), 0o644, 4, b'0').unwrap();
+        assert_eq!(
+            read_str(&header[345..500]).unwrap(),
+            format!("data/{}", "d".repeat(80))
+        );
+        assert_eq!(
+            read_str(&header[..100]).unwrap(),
+            format!("{}.json", "f".repeat(60))
+        );
+
+        let keys = keys();
+        let archive = create_archive(
+            src.path(),
+            &["data"],
+            &ArchiveKey::Operator(keys.operator_secret),
+        )
+        .unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        extract_archive(&archive, &keys, dest.path()).unwrap();
+        assert_eq!(
+            std::fs::read(dest.path().join(&long_path)).unwrap(),
+            b"long"
+        );
+    }
+
+    #[test]
+    fn rejects_paths_too_long_to_split() {
+        let path = format!("{}/{}", "d".repeat(160), "f".repeat(10));
+        assert!(header(Path::new(&path), 0o644, 0, b'0').is_err());
+    }
+
+    #[test]
+    fn round_trips_with_a_passphrase() {
+        let src = tempfile::tempdir().unwrap();
+        write(src.path(), ".charon/cluster-lock.json", b"{}", 0o644);
+
+        let keys = keys();
+        let archive =
+            create_archive(src.path(), &[".charon"], &keys.passphrase().unwrap()).unwrap();
+        assert_eq!(
+            list_archive(&archive, &keys).unwrap(),
+            [PathBuf::from(".charon/cluster-lock.json")]
+        );
+
+        let wrong = ArchiveKeys {
+            passphrase: Some(String::from("wrong")),
+            ..keys.clone()
+        };
+        assert!(list_archive(&archive, &wrong).is_err());
+
+        let without = ArchiveKeys {
+            passphrase: None,
+            ..keys
+        };
+        assert!(!without.can_decrypt(&archive).unwrap());
+        assert!(list_archive(&archive, &without).is_err());
+    }
+
+    #[test]
+    fn rejects_the_wrong_key() {
+        let src = tempfile::tempdir().unwrap();
+        write(src.path(), "file", b"secret", 0o600);
+        let keys = keys();
+
+        let archive =
+            create_archive(src.path(), &["file"], &ArchiveKey::Operator([8; 32])).unwrap();
+        assert!(list_archive(&archive, &keys).is_err());
+
+        let identity = StaticSecret::from([3; 32]);
+        let other = StaticSecret::from([4; 32]);
+        let archive = create_archive(
+            src.path(),
+            &["file"],
+            &ArchiveKey::Recipient(PublicKey::from(&identity)),
+        )
+        .unwrap();
+
+        assert!(!keys.can_decrypt(&archive).unwrap());
+        let wrong = ArchiveKeys {
+            identity: Some(other),
+            ..keys.clone()
+        };
+        assert!(list_archive(&archive, &wrong).is_err());
+        let right = ArchiveKeys {
+            identity: Some(identity),
+            ..keys
+        };
+        assert_eq!(
+            list_archive(&archive, &right).unwrap(),
+            [PathBuf::from("file")]
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_archives() {
+        let src = tempfile::tempdir().unwrap();
+        write(src.path(), "file", &[0xab; 2000], 0o600);
+        let keys = keys();
+        let archive = create_archive(
+            src.path(),
+            &["file"],
+            &ArchiveKey::Operator(keys.operator_secret),
+        )
+        .unwrap();
+
+        for len in [
+            0,
+            4,
+            MAGIC.len(),
+            MAGIC.len() + 1 + SALT_LEN,
+            archive.len() - 1,
+        ] {
+            assert!(
+                list_archive(&archive[..len], &keys).is_err(),
+                "length {len}"
+            );
+        }
+
+        // A tarball cut short inside an entry, or without its end blocks
+        let mut tar = header(Path::new("file"), 0o600, 2000, b'0')
+            .unwrap()
+            .to_vec();
+        tar.extend_from_slice(&[0xab; 1000]);
+        assert!(read_entries(&tar).is_err());
+        tar.resize(BLOCK * 5, 0xab);
+        assert!(read_entries(&tar).is_err());
+    }
+
+    #[test]
+    fn rejects_paths_leaving_the_destination() {
+        let keys = keys();
+        let key = ArchiveKey::Operator(keys.operator_secret);
+        for path in ["../escaped", "nested/../../escaped", "/absolute"] {
+            let mut tar = header(Path::new(path), 0o644, 0, b'0').unwrap().to_vec();
+            tar.extend_from_slice(&[0; 2 * BLOCK]);
+            let archive = encrypt(&tar, &key).unwrap();
+
+            let dest = tempfile::tempdir().unwrap();
+            let target = dest.path().join("inner");
+            let error = extract_archive(&archive, &keys, &target).unwrap_err();
+            assert!(error.to_string().contains("unsafe path"), "{path}: {error}");
+            assert!(!dest.path().join("escaped").exists());
+            assert!(!target.exists());
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use crate::{list_archive, ArchiveKeys, ObolContext};
+use color_eyre::eyre::{bail, eyre};
+use color_eyre::Result;
+use sha2::{Digest, Sha256};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// What a backup holds, relative to the node dir
+///
+/// The cluster lock and definition are included, so a backup can be restored with
+/// `OBOL_RESTORE_ARCHIVE`.
+pub const BACKUP_PATHS: &[&str] = &[
+    ".charon/charon-enr-private-key",
+    ".charon/validator_keys",
+    ".charon/cluster-lock.json",
+    ".charon/cluster-definition.json",
+];
+
+/// Selects every backup, see [`verify_backups`](crate::verify_backups)
+pub const ALL_BACKUPS: &str = "all";
+
+const BACKUP_PREFIX: &str = "backup-";
+const BACKUP_EXTENSION: &str = ".tar.enc";
+/// Each backup has its SHA-256 next to it, in the format of `sha256sum`
+const CHECKSUM_EXTENSION: &str = "sha256";
+
+/// Where and how often the node's keys are backed up
+#[derive(Debug, Clone)]
+pub struct BackupConfig {
+    /// `OBOL_BACKUP_DIR`, defaults to `backups` in the blueprint's data dir, which is warned about
+    pub dir: PathBuf,
+    /// `OBOL_BACKUP_INTERVAL` in seconds, defaults to a day, `0` disables periodic backups
+    pub interval: Option<Duration>,
+    /// How many backups are kept, `OBOL_BACKUP_RETENTION`, defaults to 7
+    pub retention: usize,
+}
+
+impl BackupConfig {
+    pub fn from_env(data_dir: &Path) -> Result<Self> {
+        fn number(name: &str, default: u64) -> Result<u64> {
+            match std::env::var(name) {
+                Ok(value) => value
+                    .trim()
+                    .parse()
+                    .map_err(|e| eyre!("Invalid {name} `{value}`: {e}")),
+                Err(_) => Ok(default),
+            }
+        }
+
+        let dir = std::env::var_os("OBOL_BACKUP_DIR")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| data_dir.join("backups"));
+
+        let interval = number("OBOL_BACKUP_INTERVAL", 24 * 60 * 60)?;
+        if interval > 0 && dir.starts_with(data_dir) {
+            tracing::warn!(
+                "Backing up to {}, in the data dir, the backups are lost with it. Set OBOL_BACKUP_DIR to another disk",
+                dir.display()
+            );
+        }
+        let retention = number("OBOL_BACKUP_RETENTION", 7)?;
+        if retention == 0 {
+            bail!("OBOL_BACKUP_RETENTION must keep at least one backup");
+        }
+
+        Ok(Self {
+            dir,
+            interval: (interval > 0).then(|| Duration::from_secs(interval)),
+            retention: retention as usize,
+        })
+    }
+
+    /// The backups in the backup dir, oldest first
+    pub async fn backups(&self) -> Result<Vec<PathBuf>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut backups = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            if file_name.starts_with(BACKUP_PREFIX) && file_name.ends_with(BACKUP_EXTENSION) {
+                backups.push(entry.path());
+            }
+        }
+
+        // Named after their timestamp, so they sort by age
+        backups.sort();
+        Ok(backups)
+    }
+
+    /// Write a backup and its checksum, then remove the oldest backups past the retention
+    pub async fn write(&self, archive: &[u8]) -> Result<PathBuf> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let path = self
+            .dir
+            .join(format!("{BACKUP_PREFIX}{timestamp}{BACKUP_EXTENSION}"));
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+
+        write_private(&path, archive).await?;
+        write_private(
+            &checksum_path(&path),
+            format!("{}  {file_name}\n", checksum(archive)).as_bytes(),
+        )
+        .await?;
+
+        let backups = self.backups().await?;
+        let expired = backups.len().saturating_sub(self.retention);
+        for old in &backups[..expired] {
+            tracing::info!("Removing expired backup {}", old.display());
+            tokio::fs::remove_file(old).await?;
+            let _ = tokio::fs::remove_file(checksum_path(old)).await;
+        }
+
+        Ok(path)
+    }
+}
+
+/// The hex encoded SHA-256 of `content`
+pub fn checksum(content: &[u8]) -> String {
+    hex::encode(Sha256::digest(content))
+}
+
+fn checksum_path(path: &Path) -> PathBuf {
+    let mut checksum_path = path.as_os_str().to_owned();
+    checksum_path.push(".");
+    checksum_path.push(CHECKSUM_EXTENSION);
+    PathBuf::from(checksum_path)
+}
+
+/// Check an archive against the checksum next to it, if there is one
+///
+/// Returns whether there was a checksum to check.
+pub async fn verify_checksum(path: &Path, archive: &[u8]) -> Result<bool> {
+    let checksum_path = checksum_path(path);
+    if !checksum_path.exists() {
+        return Ok(false);
+    }
+
+    let expected = tokio::fs::read_to_string(&checksum_path).await?;
+    let expected = expected.split_whitespace().next().unwrap_or_default();
+    let actual = checksum(archive);
+    if !expected.eq_ignore_ascii_case(&actual) {
+        bail!(
+            "Checksum mismatch for {}, expected {expected}, got {actual}",
+            path.display()
+        );
+    }
+
+    Ok(true)
+}
+
+/// Check a backup can be restored: its checksum matches, it decrypts with `keys`, and it holds the
+/// ENR private key
+///
+/// Returns the files in the backup, or `None` if there's no key to decrypt it with, such as for
+/// backups encrypted to `OBOL_BACKUP_RECIPIENT` on a host without its identity. Then only the
+/// checksum and the archive's header are checked.
+pub async fn verify_backup(path: &Path, keys: &ArchiveKeys) -> Result<Option<Vec<PathBuf>>> {
+    let archive = tokio::fs::read(path)
+        .await
+        .map_err(|e| eyre!("Failed to read {}: {e}", path.display()))?;
+
+    if !verify_checksum(path, &archive).await? {
+        bail!("{} has no checksum", path.display());
+    }
+
+    if !keys.can_decrypt(&archive)? {
+        return Ok(None);
+    }
+
+    let files = list_archive(&archive, keys)?;
+    if !files.iter().any(|file| file.ends_with(BACKUP_PATHS[0])) {
+        bail!("{} has no ENR private key", path.display());
+    }
+
+    Ok(Some(files))
+}
+
+/// Back up the node every [`BackupConfig::interval`], logging failures
+pub async fn run_backups(ctx: Arc<ObolContext>, config: BackupConfig) {
+    let Some(interval) = config.interval else {
+        tracing::info!("Periodic backups are disabled");
+        return;
+    };
+
+    let mut interval = tokio::time::interval(interval);
+    loop {
+        interval.tick().await;
+        match ctx.dv_operator.backup(&config).await {
+            Ok(path) => tracing::info!("Backed up the node to {}", path.display()),
+            Err(e) => tracing::error!("Failed to back up the node: {e:?}"),
+        }
+    }
+}
+
+async fn write_private(path: &Path, content: &[u8]) -> Result<()> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    tokio::fs::write(&tmp_path, content).await?;
+    tokio::fs::set_permissions(&tmp_path, std::fs::Permissions::from_mode(0o600)).await?;
+    tokio::fs::rename(&tmp_path, path).await?;
+
+    Ok(())
+}
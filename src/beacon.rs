@@ -0,0 +1,81 @@
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// The statuses of a validator that can no longer be asked to attest or propose
+const EXITED_STATUSES: &[&str] = &[
+    "exited_unslashed",
+    "exited_slashed",
+    "withdrawal_possible",
+    "withdrawal_done",
+];
+
+#[derive(Deserialize)]
+struct ValidatorsResponse {
+    data: Vec<ValidatorResponse>,
+}
+
+#[derive(Deserialize)]
+struct ValidatorResponse {
+    status: String,
+    validator: Validator,
+}
+
+#[derive(Deserialize)]
+struct Validator {
+    pubkey: String,
+}
+
+/// Fetch the status of each of `pubkeys` at the head of the chain, from the first of `endpoints`
+/// that answers
+///
+/// Validators the beacon node doesn't know about, such as those that were never deposited, are
+/// left out.
+pub async fn validator_statuses(
+    endpoints: &[String],
+    pubkeys: &[&str],
+) -> Result<HashMap<String, String>> {
+    let client = reqwest::Client::new();
+    let ids = pubkeys.join(",");
+
+    let mut last_error = eyre!("No beacon node endpoints configured");
+    for endpoint in endpoints {
+        let url = format!(
+            "{}/eth/v1/beacon/states/head/validators",
+            endpoint.trim_end_matches('/')
+        );
+        let response = client
+            .get(&url)
+            .query(&[("id", &ids)])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+
+        let response = match response {
+            Ok(response) => response.json::<ValidatorsResponse>().await,
+            Err(e) => Err(e),
+        };
+
+        match response {
+            Ok(response) => {
+                return Ok(response
+                    .data
+                    .into_iter()
+                    .map(|v| (v.validator.pubkey.to_lowercase(), v.status))
+                    .collect());
+            }
+            Err(e) => {
+                tracing::warn!("Failed to fetch validator statuses from {endpoint}: {e}");
+                last_error = eyre!("Failed to fetch validator statuses from {endpoint}: {e}");
+            }
+        }
+    }
+
+    Err(last_error)
+}
+
+/// Whether a validator with `status` has fully exited
+pub fn is_exited(status: &str) -> bool {
+    EXITED_STATUSES.contains(&status)
+}
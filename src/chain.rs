@@ -0,0 +1,307 @@
+use crate::check_hex;
+use color_eyre::eyre::{bail, eyre};
+use color_eyre::Result;
+use std::fmt;
+
+/// The names of the networks charon knows about
+const PUBLIC_NETWORKS: &[&str] = &["mainnet", "holesky", "sepolia"];
+
+/// The Ethereum network a cluster validates on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Network {
+    Mainnet,
+    Holesky,
+    Sepolia,
+    /// A network charon doesn't know about, such as a local devnet
+    ///
+    /// The validator clients don't know about it either, so only the DKG ceremony can be run on it.
+    Devnet {
+        name: String,
+        /// The `0x` prefixed 4 byte genesis fork version
+        genesis_fork_version: String,
+        chain_id: u64,
+        /// Unix timestamp of the genesis
+        genesis_timestamp: u64,
+        /// The `0x` prefixed address of the deposit contract
+        deposit_contract: String,
+    },
+}
+
+impl Network {
+    /// Read the network from `OBOL_NETWORK`, defaulting to Holesky
+    ///
+    /// `OBOL_NETWORK=devnet` also requires `OBOL_DEVNET_NAME`, `OBOL_DEVNET_FORK_VERSION`,
+    /// `OBOL_DEVNET_CHAIN_ID`, `OBOL_DEVNET_GENESIS_TIMESTAMP` and `OBOL_DEVNET_DEPOSIT_CONTRACT`.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// [`Network::from_env`], with the variables looked up by `lookup`
+    fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let var = |name: &str| lookup(name).ok_or_else(|| eyre!("{name} is required for a devnet"));
+        let number = |name: &str| -> Result<u64> {
+            let value = var(name)?;
+            value
+                .parse()
+                .map_err(|e| eyre!("Invalid {name} `{value}`: {e}"))
+        };
+
+        let name = lookup("OBOL_NETWORK").unwrap_or_else(|| String::from("holesky"));
+        let network = match name.as_str() {
+            "mainnet" => Network::Mainnet,
+            "holesky" => Network::Holesky,
+            "sepolia" => Network::Sepolia,
+            "devnet" => Network::Devnet {
+                name: var("OBOL_DEVNET_NAME")?,
+                genesis_fork_version: var("OBOL_DEVNET_FORK_VERSION")?,
+                chain_id: number("OBOL_DEVNET_CHAIN_ID")?,
+                genesis_timestamp: number("OBOL_DEVNET_GENESIS_TIMESTAMP")?,
+                deposit_contract: var("OBOL_DEVNET_DEPOSIT_CONTRACT")?,
+            },
+            _ => {
+                bail!("Unsupported network `{name}`, expected mainnet, holesky, sepolia or devnet")
+            }
+        };
+
+        network.validate()?;
+        Ok(network)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if let Network::Devnet {
+            name,
+            genesis_fork_version,
+            deposit_contract,
+            ..
+        } = self
+        {
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+                bail!("Invalid devnet name `{name}`");
+            }
+
+            if PUBLIC_NETWORKS.contains(&name.as_str()) {
+                bail!("Devnet name `{name}` is taken by a public network");
+            }
+
+            check_hex("genesis fork version", genesis_fork_version, 4)?;
+            check_hex("deposit contract", deposit_contract, 20)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Network::Mainnet => "mainnet",
+            Network::Holesky => "holesky",
+            Network::Sepolia => "sepolia",
+            Network::Devnet { name, .. } => name,
+        }
+    }
+
+    /// The `0x` prefixed genesis fork version, as found in cluster definitions and deposit data
+    pub fn genesis_fork_version(&self) -> &str {
+        match self {
+            Network::Mainnet => "0x00000000",
+            Network::Holesky => "0x01017000",
+            Network::Sepolia => "0x90000069",
+            Network::Devnet {
+                genesis_fork_version,
+                ..
+            } => genesis_fork_version,
+        }
+    }
+
+    /// The `0x` prefixed address the validators' deposits are sent to
+    pub fn deposit_contract(&self) -> &str {
+        match self {
+            Network::Mainnet => "0x00000000219ab540356cBB839Cbe05303d7705Fa",
+            Network::Holesky => "0x4242424242424242424242424242424242424242",
+            Network::Sepolia => "0x7f02C3E3c98b133055B8B348B2Ac625669Ed295D",
+            Network::Devnet {
+                deposit_contract, ..
+            } => deposit_contract,
+        }
+    }
+
+    /// The arguments selecting this network for `charon create dkg`
+    pub fn charon_args(&self) -> Vec<String> {
+        match self {
+            Network::Devnet {
+                name,
+                genesis_fork_version,
+                chain_id,
+                genesis_timestamp,
+                ..
+            } => vec![
+                String::from("--testnet-name"),
+                name.clone(),
+                String::from("--testnet-fork-version"),
+                genesis_fork_version.clone(),
+                String::from("--testnet-chain-id"),
+                chain_id.to_string(),
+                String::from("--testnet-genesis-timestamp"),
+                genesis_timestamp.to_string(),
+            ],
+            _ => vec![String::from("--network"), self.name().to_string()],
+        }
+    }
+
+    /// The argument selecting this network for MEV-boost, which only runs on public networks
+    pub fn mev_boost_args(&self) -> String {
+        format!("-{}", self.name())
+    }
+
+    /// Whether a fork version, with or without the `0x` prefix, is this network's
+    pub fn matches_fork_version(&self, fork_version: &str) -> bool {
+        let expected = &self.genesis_fork_version()[2..];
+        let fork_version = fork_version.strip_prefix("0x").unwrap_or(fork_version);
+        fork_version.eq_ignore_ascii_case(expected)
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn parse(vars: &[(&str, &str)]) -> Result<Network> {
+        let vars = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<HashMap<_, _>>();
+        Network::from_vars(|name| vars.get(name).cloned())
+    }
+
+    fn devnet_vars() -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("OBOL_NETWORK", "devnet"),
+            ("OBOL_DEVNET_NAME", "kurtosis-1"),
+            ("OBOL_DEVNET_FORK_VERSION", "0x10000038"),
+            ("OBOL_DEVNET_CHAIN_ID", "3151908"),
+            ("OBOL_DEVNET_GENESIS_TIMESTAMP", "1700000000"),
+            (
+                "OBOL_DEVNET_DEPOSIT_CONTRACT",
+                "0x4242424242424242424242424242424242424242",
+            ),
+        ]
+    }
+
+    #[test]
+    fn parses_public_networks() {
+        assert_eq!(parse(&[]).unwrap(), Network::Holesky);
+        assert_eq!(
+            parse(&[("OBOL_NETWORK", "mainnet")]).unwrap(),
+            Network::Mainnet
+        );
+        assert_eq!(
+            parse(&[("OBOL_NETWORK", "sepolia")]).unwrap(),
+            Network::Sepolia
+        );
+        assert!(parse(&[("OBOL_NETWORK", "hoodi")]).is_err());
+        assert!(parse(&[("OBOL_NETWORK", "Mainnet")]).is_err());
+    }
+
+    #[test]
+    fn parses_devnets() {
+        let network = parse(&devnet_vars()).unwrap();
+        assert_eq!(
+            network,
+            Network::Devnet {
+                name: String::from("kurtosis-1"),
+                genesis_fork_version: String::from("0x10000038"),
+                chain_id: 3151908,
+                genesis_timestamp: 1700000000,
+                deposit_contract: String::from("0x4242424242424242424242424242424242424242"),
+            }
+        );
+        assert_eq!(network.name(), "kurtosis-1");
+        assert_eq!(
+            network.charon_args(),
+            [
+                "--testnet-name",
+                "kurtosis-1",
+                "--testnet-fork-version",
+                "0x10000038",
+                "--testnet-chain-id",
+                "3151908",
+                "--testnet-genesis-timestamp",
+                "1700000000",
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_devnets() {
+        let invalid = [
+            ("OBOL_DEVNET_NAME", "mainnet"),
+            ("OBOL_DEVNET_NAME", "my devnet"),
+            ("OBOL_DEVNET_NAME", ""),
+            ("OBOL_DEVNET_FORK_VERSION", "10000038"),
+            ("OBOL_DEVNET_FORK_VERSION", "0x1000"),
+            ("OBOL_DEVNET_CHAIN_ID", "chain"),
+            ("OBOL_DEVNET_GENESIS_TIMESTAMP", "-1"),
+            ("OBOL_DEVNET_DEPOSIT_CONTRACT", "0x4242"),
+        ];
+
+        for (name, value) in invalid {
+            let mut vars = devnet_vars();
+            vars.retain(|(var, _)| *var != name);
+            vars.push((name, value));
+            assert!(parse(&vars).is_err(), "{name}={value}");
+        }
+
+        // Every variable is required
+        for i in 1..devnet_vars().len() {
+            let mut vars = devnet_vars();
+            let (name, _) = vars.remove(i);
+            let error = parse(&vars).unwrap_err();
+            assert!(error.to_string().contains(name), "{error}");
+        }
+    }
+
+    #[test]
+    fn public_network_values() {
+        let expected = [
+            (
+                Network::Mainnet,
+                "0x00000000",
+                "0x00000000219ab540356cBB839Cbe05303d7705Fa",
+            ),
+            (
+                Network::Holesky,
+                "0x01017000",
+                "0x4242424242424242424242424242424242424242",
+            ),
+            (
+                Network::Sepolia,
+                "0x90000069",
+                "0x7f02C3E3c98b133055B8B348B2Ac625669Ed295D",
+            ),
+        ];
+
+        for (network, fork_version, deposit_contract) in expected {
+            assert_eq!(network.genesis_fork_version(), fork_version);
+            assert_eq!(network.deposit_contract(), deposit_contract);
+            assert_eq!(network.charon_args(), ["--network", network.name()]);
+            assert_eq!(network.mev_boost_args(), format!("-{network}"));
+        }
+    }
+
+    #[test]
+    fn matches_fork_versions() {
+        assert!(Network::Holesky.matches_fork_version("0x01017000"));
+        assert!(Network::Holesky.matches_fork_version("01017000"));
+        assert!(!Network::Mainnet.matches_fork_version("0x01017000"));
+
+        let devnet = parse(&devnet_vars()).unwrap();
+        assert!(devnet.matches_fork_version("0x10000038"));
+        assert!(!devnet.matches_fork_version("0x10000039"));
+    }
+}
@@ -0,0 +1,528 @@
+use crate::DkgConfig;
+use color_eyre::eyre::{bail, eyre};
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::str::FromStr;
+
+/// A parsed `cluster-definition.json`, as produced by `charon create dkg`
+///
+/// The original file contents are kept alongside the parsed fields, so the definition can be
+/// written back to disk byte-for-byte (charon verifies the definition hash against them).
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClusterDefinition {
+    pub name: String,
+    pub creator: Creator,
+    pub operators: Vec<DefinitionOperator>,
+    pub uuid: String,
+    pub version: String,
+    pub num_validators: u32,
+    pub threshold: u32,
+    pub validators: Vec<DefinitionValidator>,
+    pub fork_version: String,
+    pub config_hash: String,
+    pub definition_hash: String,
+    #[serde(skip)]
+    raw: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Creator {
+    pub address: String,
+    pub config_signature: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefinitionOperator {
+    pub address: String,
+    pub enr: String,
+    pub config_signature: String,
+    pub enr_signature: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefinitionValidator {
+    pub fee_recipient_address: String,
+    pub withdrawal_address: String,
+}
+
+impl ClusterDefinition {
+    pub fn from_json(raw: String) -> Result<Self> {
+        let mut definition: ClusterDefinition = serde_json::from_str(&raw)?;
+        definition.raw = raw;
+        definition.validate()?;
+
+        Ok(definition)
+    }
+
+    /// The definition exactly as it was read
+    pub fn as_json(&self) -> &str {
+        &self.raw
+    }
+
+    pub fn enrs(&self) -> impl Iterator<Item = &str> {
+        self.operators.iter().map(|op| op.enr.as_str())
+    }
+
+    pub fn fee_recipients(&self) -> impl Iterator<Item = &str> {
+        self.validators
+            .iter()
+            .map(|v| v.fee_recipient_address.as_str())
+    }
+
+    pub fn withdrawal_addresses(&self) -> impl Iterator<Item = &str> {
+        self.validators
+            .iter()
+            .map(|v| v.withdrawal_address.as_str())
+    }
+
+    pub fn contains_enr(&self, enr: &str) -> bool {
+        self.enrs().any(|e| e == enr.trim())
+    }
+
+    /// Checks that the definition is the one this operator expects to join
+    ///
+    /// `my_enr` must be among the operators, the operator set must be exactly `operator_count`
+    /// distinct ENRs, namely `my_enr` and the other operators' ENRs in `expected`, and the name,
+    /// network and validators must match those requested in `expected`.
+    pub fn verify(&self, my_enr: &str, operator_count: usize, expected: &DkgConfig) -> Result<()> {
+        if self.name != expected.name {
+            bail!(
+                "Definition is for cluster `{}`, expected `{}`",
+                self.name,
+                expected.name
+            );
+        }
+
+        if !self.contains_enr(my_enr) {
+            bail!("Definition does not include our ENR");
+        }
+
+        if self.operators.len() != operator_count {
+            bail!(
+                "Definition has {} operators, expected {operator_count}",
+                self.operators.len()
+            );
+        }
+
+        let unique_enrs = self.enrs().collect::<HashSet<_>>();
+        if unique_enrs.len() != self.operators.len() {
+            bail!("Definition contains duplicate operator ENRs");
+        }
+
+        let expected_enrs = expected
+            .enrs
+            .iter()
+            .map(|enr| enr.trim())
+            .chain([my_enr.trim()])
+            .collect::<HashSet<_>>();
+        let mut unexpected = unique_enrs
+            .difference(&expected_enrs)
+            .copied()
+            .collect::<Vec<_>>();
+        if !unexpected.is_empty() {
+            unexpected.sort();
+            bail!(
+                "Definition contains ENRs the operators didn't send: {}",
+                unexpected.join(", ")
+            );
+        }
+
+        if !expected.network.matches_fork_version(&self.fork_version) {
+            bail!(
+                "Definition has fork version {}, expected {} for {}",
+                self.fork_version,
+                expected.network.genesis_fork_version(),
+                expected.network
+            );
+        }
+
+        if self.num_validators != expected.validator_count {
+            bail!(
+                "Definition has {} validators, expected {}",
+                self.num_validators,
+                expected.validator_count
+            );
+        }
+
+        let expected_validators = expected
+            .fee_recipients
+            .iter()
+            .zip(&expected.withdrawal_addresses);
+        for (i, (validator, (fee_recipient, withdrawal_address))) in
+            self.validators.iter().zip(expected_validators).enumerate()
+        {
+            if !validator
+                .fee_recipient_address
+                .eq_ignore_ascii_case(fee_recipient)
+            {
+                bail!(
+                    "Validator #{i} has unexpected fee recipient {}",
+                    validator.fee_recipient_address
+                );
+            }
+
+            if !validator
+                .withdrawal_address
+                .eq_ignore_ascii_case(withdrawal_address)
+            {
+                bail!(
+                    "Validator #{i} has unexpected withdrawal address {}",
+                    validator.withdrawal_address
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.name.is_empty() {
+            bail!("Cluster definition has no name");
+        }
+
+        if self.operators.is_empty() {
+            bail!("Cluster definition has no operators");
+        }
+
+        for (i, operator) in self.operators.iter().enumerate() {
+            if !operator.enr.starts_with("enr:") {
+                bail!("Operator #{i} has an invalid ENR: {}", operator.enr);
+            }
+        }
+
+        if self.threshold == 0 || self.threshold as usize > self.operators.len() {
+            bail!(
+                "Invalid threshold {} for {} operators",
+                self.threshold,
+                self.operators.len()
+            );
+        }
+
+        if self.num_validators == 0 || self.num_validators as usize != self.validators.len() {
+            bail!(
+                "Expected {} validators, found {}",
+                self.num_validators,
+                self.validators.len()
+            );
+        }
+
+        for validator in &self.validators {
+            check_hex(
+                "fee recipient address",
+                &validator.fee_recipient_address,
+                20,
+            )?;
+            check_hex("withdrawal address", &validator.withdrawal_address, 20)?;
+        }
+
+        check_hex("fork version", &self.fork_version, 4)?;
+        check_hex("config hash", &self.config_hash, 32)?;
+        check_hex("definition hash", &self.definition_hash, 32)?;
+
+        Ok(())
+    }
+}
+
+impl FromStr for ClusterDefinition {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::from_json(s.to_string())
+    }
+}
+
+/// A parsed `cluster-lock.json`, as produced by the DKG ceremony
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClusterLock {
+    pub cluster_definition: LockDefinition,
+    pub lock_hash: String,
+    pub distributed_validators: Vec<DistributedValidator>,
+}
+
+/// The cluster definition the lock was created from, only as far as it's needed here
+#[derive(Debug, Clone, Deserialize)]
+pub struct LockDefinition {
+    pub operators: Vec<DefinitionOperator>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DistributedValidator {
+    pub distributed_public_key: String,
+    pub public_shares: Vec<String>,
+}
+
+impl ClusterLock {
+    pub fn from_json(raw: &str) -> Result<Self> {
+        let lock: ClusterLock = serde_json::from_str(raw)?;
+
+        check_hex("lock hash", &lock.lock_hash, 32)?;
+        if lock.distributed_validators.is_empty() {
+            bail!("Cluster lock has no validators");
+        }
+
+        for validator in &lock.distributed_validators {
+            check_hex(
+                "validator public key",
+                &validator.distributed_public_key,
+                48,
+            )?;
+        }
+
+        Ok(lock)
+    }
+
+    pub fn enrs(&self) -> impl Iterator<Item = &str> {
+        self.cluster_definition
+            .operators
+            .iter()
+            .map(|op| op.enr.as_str())
+    }
+
+    pub fn contains_enr(&self, enr: &str) -> bool {
+        self.enrs().any(|e| e == enr.trim())
+    }
+
+    pub fn validator_pubkeys(&self) -> impl Iterator<Item = &str> {
+        self.distributed_validators
+            .iter()
+            .map(|v| v.distributed_public_key.as_str())
+    }
+}
+
+/// Checks that `value` is a `0x`-prefixed hex string of `len` bytes
+pub(crate) fn check_hex(what: &str, value: &str, len: usize) -> Result<()> {
+    let digits = value
+        .strip_prefix("0x")
+        .ok_or_else(|| eyre!("Invalid {what} `{value}`, missing 0x prefix"))?;
+
+    if digits.len() != len * 2 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        bail!("Invalid {what} `{value}`, expected {len} hex encoded bytes");
+    }
+
+    Ok(())
+}
+
+/// (De)serializes a [`ClusterDefinition`] as its original JSON text, for use in protocol messages
+pub(crate) mod raw_json {
+    use super::ClusterDefinition;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        definition: &ClusterDefinition,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(definition.as_json())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Box<ClusterDefinition>, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        ClusterDefinition::from_json(raw)
+            .map(Box::new)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Network;
+
+    const ENRS: [&str; 4] = [
+        "enr:-HW4QFIRST",
+        "enr:-HW4QSECOND",
+        "enr:-HW4QTHIRD",
+        "enr:-HW4QFOURTH",
+    ];
+    const FEE_RECIPIENT: &str = "0x000000000000000000000000000000000000dEaD";
+    const WITHDRAWAL_ADDRESS: &str = "0x1111111111111111111111111111111111111111";
+
+    /// A definition in charon's layout, with fields this crate doesn't parse and uneven spacing
+    fn definition_json(enrs: &[&str], fork_version: &str) -> String {
+        let operators = enrs
+            .iter()
+            .map(|enr| {
+                format!(
+                    r#"{{"address": "", "enr": "{enr}", "config_signature": "", "enr_signature": ""}}"#
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",\n    ");
+
+        format!(
+            r#"{{
+  "name": "test-cluster",
+  "creator": {{"address": "", "config_signature": ""}},
+  "operators": [
+    {operators}
+  ],
+  "uuid": "5B8B0D6E-3B5E-4D5C-9C3A-6F4C2B1A0E9D",
+  "version": "v1.8.0",
+  "timestamp": "2024-10-13T00:00:00Z",
+  "num_validators": 2,
+  "threshold":   3,
+  "validators": [
+    {{"fee_recipient_address": "{FEE_RECIPIENT}", "withdrawal_address": "{WITHDRAWAL_ADDRESS}"}},
+    {{"fee_recipient_address": "{FEE_RECIPIENT}", "withdrawal_address": "{WITHDRAWAL_ADDRESS}"}}
+  ],
+  "dkg_algorithm": "default",
+  "fork_version": "{fork_version}",
+  "deposit_amounts": null,
+  "config_hash": "0x{config_hash}",
+  "definition_hash": "0x{definition_hash}"
+}}
+"#,
+            config_hash = "ab".repeat(32),
+            definition_hash = "cd".repeat(32),
+        )
+    }
+
+    fn definition() -> ClusterDefinition {
+        ClusterDefinition::from_json(definition_json(&ENRS, "0x01017000")).unwrap()
+    }
+
+    /// The config the operator with the first ENR expects
+    fn expected() -> DkgConfig {
+        let mut config = DkgConfig::new(
+            String::from("test-cluster"),
+            Network::Holesky,
+            2,
+            vec![FEE_RECIPIENT.to_lowercase(); 2],
+            vec![WITHDRAWAL_ADDRESS.to_string(); 2],
+        )
+        .unwrap();
+        config.enrs = ENRS[1..].iter().rev().map(|enr| enr.to_string()).collect();
+        config
+    }
+
+    #[test]
+    fn from_json_parses_a_definition() {
+        let definition = definition();
+        assert_eq!(definition.name, "test-cluster");
+        assert_eq!(definition.threshold, 3);
+        assert_eq!(definition.num_validators, 2);
+        assert_eq!(definition.enrs().collect::<Vec<_>>(), ENRS);
+        assert_eq!(
+            definition.fee_recipients().collect::<Vec<_>>(),
+            [FEE_RECIPIENT; 2]
+        );
+        assert!(definition.contains_enr(&format!("{}\n", ENRS[2])));
+        assert!(!definition.contains_enr("enr:-HW4QOTHER"));
+    }
+
+    #[test]
+    fn as_json_is_the_original_text() {
+        let raw = definition_json(&ENRS, "0x01017000");
+        let definition = ClusterDefinition::from_json(raw.clone()).unwrap();
+        assert_eq!(definition.as_json(), raw);
+        assert_eq!(raw.parse::<ClusterDefinition>().unwrap().as_json(), raw);
+
+        // As sent in protocol messages
+        #[derive(Serialize, Deserialize)]
+        struct Message(#[serde(with = "raw_json")] Box<ClusterDefinition>);
+        let sent = serde_json::to_vec(&Message(Box::new(definition))).unwrap();
+        let received: Message = serde_json::from_slice(&sent).unwrap();
+        assert_eq!(received.0.as_json(), raw);
+    }
+
+    #[test]
+    fn from_json_rejects_invalid_definitions() {
+        let valid = definition_json(&ENRS, "0x01017000");
+        let invalid = [
+            valid.replace(ENRS[1], "not-an-enr"),
+            valid.replace(r#""threshold":   3"#, r#""threshold":   5"#),
+            valid.replace(r#""threshold":   3"#, r#""threshold":   0"#),
+            valid.replace(r#""num_validators": 2"#, r#""num_validators": 3"#),
+            valid.replace(FEE_RECIPIENT, "0xdead"),
+            valid.replace(r#""name": "test-cluster""#, r#""name": """#),
+            definition_json(&ENRS, "01017000"),
+            definition_json(&[], "0x01017000"),
+            valid.replace(r#""uuid""#, r#""id""#),
+        ];
+
+        for raw in invalid {
+            assert!(ClusterDefinition::from_json(raw.clone()).is_err(), "{raw}");
+        }
+    }
+
+    #[test]
+    fn verify_accepts_the_expected_definition() {
+        definition().verify(ENRS[0], 4, &expected()).unwrap();
+    }
+
+    #[test]
+    fn verify_requires_our_enr() {
+        let error = definition()
+            .verify("enr:-HW4QOTHER", 4, &expected())
+            .unwrap_err();
+        assert!(error.to_string().contains("our ENR"), "{error}");
+    }
+
+    #[test]
+    fn verify_checks_the_operator_count() {
+        let three_operators =
+            ClusterDefinition::from_json(definition_json(&ENRS[..3], "0x01017000")).unwrap();
+        assert!(three_operators.verify(ENRS[0], 4, &expected()).is_err());
+        assert!(definition().verify(ENRS[0], 5, &expected()).is_err());
+    }
+
+    #[test]
+    fn verify_rejects_duplicate_enrs() {
+        let enrs = [ENRS[0], ENRS[1], ENRS[1], ENRS[2]];
+        let definition =
+            ClusterDefinition::from_json(definition_json(&enrs, "0x01017000")).unwrap();
+        let error = definition.verify(ENRS[0], 4, &expected()).unwrap_err();
+        assert!(error.to_string().contains("duplicate"), "{error}");
+    }
+
+    #[test]
+    fn verify_compares_every_operator_enr() {
+        // The leader swapped another operator's ENR for one of its own
+        let enrs = [ENRS[0], ENRS[1], ENRS[2], "enr:-HW4QIMPOSTOR"];
+        let definition =
+            ClusterDefinition::from_json(definition_json(&enrs, "0x01017000")).unwrap();
+        let error = definition.verify(ENRS[0], 4, &expected()).unwrap_err();
+        assert!(error.to_string().contains("enr:-HW4QIMPOSTOR"), "{error}");
+    }
+
+    #[test]
+    fn verify_checks_the_name() {
+        let config = DkgConfig {
+            name: String::from("other-cluster"),
+            ..expected()
+        };
+        let error = definition().verify(ENRS[0], 4, &config).unwrap_err();
+        assert!(error.to_string().contains("other-cluster"), "{error}");
+    }
+
+    #[test]
+    fn verify_checks_the_network() {
+        let definition =
+            ClusterDefinition::from_json(definition_json(&ENRS, "0x00000000")).unwrap();
+        let error = definition.verify(ENRS[0], 4, &expected()).unwrap_err();
+        assert!(error.to_string().contains("fork version"), "{error}");
+    }
+
+    #[test]
+    fn verify_checks_the_validators() {
+        let expected = expected();
+
+        let config = DkgConfig {
+            validator_count: 3,
+            ..expected.clone()
+        };
+        assert!(definition().verify(ENRS[0], 4, &config).is_err());
+
+        let mut config = expected.clone();
+        config.fee_recipients[1] = WITHDRAWAL_ADDRESS.to_string();
+        let error = definition().verify(ENRS[0], 4, &config).unwrap_err();
+        assert!(error.to_string().contains("fee recipient"), "{error}");
+
+        let mut config = expected;
+        config.withdrawal_addresses[0] = FEE_RECIPIENT.to_string();
+        let error = definition().verify(ENRS[0], 4, &config).unwrap_err();
+        assert!(error.to_string().contains("withdrawal address"), "{error}");
+    }
+}
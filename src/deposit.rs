@@ -0,0 +1,161 @@
+use crate::{check_hex, Network};
+use color_eyre::eyre::bail;
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+
+/// Selects every validator in the cluster, for jobs that take a validator public key
+pub const ALL_VALIDATORS: &str = "all";
+
+/// An entry of the `deposit-data.json` generated by the DKG ceremony
+///
+/// The byte fields are hex encoded, without a `0x` prefix, as in the file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DepositData {
+    pub pubkey: String,
+    pub withdrawal_credentials: String,
+    pub amount: u64,
+    pub signature: String,
+    pub deposit_data_root: String,
+    /// The network the deposit is for, only missing from files written by older charon versions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fork_version: Option<String>,
+}
+
+impl DepositData {
+    /// Parse the contents of a `deposit-data.json` file, with deposits for `network`
+    pub fn parse_file(content: &str, network: &Network) -> Result<Vec<DepositData>> {
+        let entries: Vec<DepositData> = serde_json::from_str(content)?;
+        if entries.is_empty() {
+            bail!("Deposit data file has no entries");
+        }
+
+        for entry in &entries {
+            entry.validate(network)?;
+        }
+
+        Ok(entries)
+    }
+
+    /// Whether this deposit is for the validator `pubkey`, or `pubkey` is [`ALL_VALIDATORS`]
+    pub fn matches(&self, pubkey: &str) -> bool {
+        if pubkey == ALL_VALIDATORS {
+            return true;
+        }
+
+        let pubkey = pubkey.strip_prefix("0x").unwrap_or(pubkey);
+        self.pubkey.eq_ignore_ascii_case(pubkey)
+    }
+
+    fn validate(&self, network: &Network) -> Result<()> {
+        check_hex("deposit pubkey", &format!("0x{}", self.pubkey), 48)?;
+        check_hex(
+            "withdrawal credentials",
+            &format!("0x{}", self.withdrawal_credentials),
+            32,
+        )?;
+        check_hex("deposit signature", &format!("0x{}", self.signature), 96)?;
+        check_hex(
+            "deposit data root",
+            &format!("0x{}", self.deposit_data_root),
+            32,
+        )?;
+
+        if let Some(fork_version) = &self.fork_version {
+            if !network.matches_fork_version(fork_version) {
+                bail!(
+                    "Deposit for {} has fork version {fork_version}, expected {} for {network}",
+                    self.pubkey,
+                    network.genesis_fork_version()
+                );
+            }
+        }
+
+        if self.amount == 0 {
+            bail!("Deposit for {} has no amount", self.pubkey);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(pubkey: &str, amount: u64, fork_version: Option<&str>) -> String {
+        let fork_version = fork_version
+            .map(|fork_version| format!(r#", "fork_version": "{fork_version}""#))
+            .unwrap_or_default();
+        format!(
+            r#"{{"pubkey": "{pubkey}", "withdrawal_credentials": "{}", "amount": {amount}, "signature": "{}", "deposit_data_root": "{}", "deposit_message_root": "{}", "network_name": "holesky"{fork_version}}}"#,
+            "01".repeat(32),
+            "02".repeat(96),
+            "03".repeat(32),
+            "04".repeat(32),
+        )
+    }
+
+    fn file(entries: &[String]) -> String {
+        format!("[{}]", entries.join(", "))
+    }
+
+    #[test]
+    fn parse_file_reads_every_entry() {
+        let pubkeys = ["aa".repeat(48), "bb".repeat(48)];
+        let content = file(&[
+            entry(&pubkeys[0], 32_000_000_000, Some("01017000")),
+            entry(&pubkeys[1], 32_000_000_000, None),
+        ]);
+
+        let deposits = DepositData::parse_file(&content, &Network::Holesky).unwrap();
+        assert_eq!(deposits.len(), 2);
+        assert_eq!(deposits[0].pubkey, pubkeys[0]);
+        assert_eq!(deposits[0].amount, 32_000_000_000);
+        assert_eq!(deposits[0].fork_version.as_deref(), Some("01017000"));
+        assert_eq!(deposits[1].fork_version, None);
+    }
+
+    #[test]
+    fn parse_file_checks_the_network() {
+        let content = file(&[entry(&"aa".repeat(48), 32_000_000_000, Some("01017000"))]);
+        let error = DepositData::parse_file(&content, &Network::Mainnet).unwrap_err();
+        assert!(error.to_string().contains("fork version"), "{error}");
+    }
+
+    #[test]
+    fn parse_file_rejects_invalid_entries() {
+        let invalid = [
+            file(&[]),
+            file(&[entry(&"aa".repeat(48), 0, None)]),
+            file(&[entry(&"aa".repeat(47), 32_000_000_000, None)]),
+            file(&[entry(
+                &format!("0x{}", "aa".repeat(48)),
+                32_000_000_000,
+                None,
+            )]),
+            file(&[entry(&"zz".repeat(48), 32_000_000_000, None)]),
+            file(&[
+                entry(&"aa".repeat(48), 32_000_000_000, None),
+                entry(&"bb".repeat(48), 0, None),
+            ]),
+        ];
+
+        for content in invalid {
+            assert!(
+                DepositData::parse_file(&content, &Network::Holesky).is_err(),
+                "{content}"
+            );
+        }
+    }
+
+    #[test]
+    fn matches_a_pubkey_or_all() {
+        let content = file(&[entry(&"aa".repeat(48), 32_000_000_000, None)]);
+        let deposit = &DepositData::parse_file(&content, &Network::Holesky).unwrap()[0];
+
+        assert!(deposit.matches(ALL_VALIDATORS));
+        assert!(deposit.matches(&"aa".repeat(48)));
+        assert!(deposit.matches(&format!("0x{}", "AA".repeat(48))));
+        assert!(!deposit.matches(&"bb".repeat(48)));
+    }
+}
@@ -0,0 +1,274 @@
+use crate::{validator_client, Network, ValidatorClient};
+use color_eyre::eyre::{bail, eyre};
+use color_eyre::Result;
+
+/// The log levels charon accepts
+const LOG_LEVELS: &[&str] = &["debug", "info", "warn", "error"];
+
+/// The configuration of the distributed validator node, which its containers are started with,
+/// see [`ValidatorStack`](crate::ValidatorStack)
+///
+/// Read from `OBOL_*` environment variables of the blueprint, see [`CharonEnv::from_env`].
+#[derive(Debug, Clone)]
+pub struct CharonEnv {
+    /// See [`Network::from_env`]
+    pub network: Network,
+    /// The consensus client APIs charon connects to, `OBOL_BEACON_NODE_ENDPOINTS` (comma separated)
+    pub beacon_node_endpoints: Vec<String>,
+    /// `OBOL_VALIDATOR_CLIENT`, defaults to `lighthouse`, see [`VALIDATOR_CLIENTS`](crate::VALIDATOR_CLIENTS)
+    pub validator_client: &'static dyn ValidatorClient,
+    /// The execution client JSON-RPC API, `OBOL_EXECUTION_ENDPOINT`
+    pub execution_endpoint: Option<String>,
+    /// MEV-boost relays, `OBOL_MEV_BOOST_RELAYS` (comma separated), enables the builder API if set
+    pub mev_boost_relays: Vec<String>,
+    /// `OBOL_P2P_TCP_PORT`, defaults to `3610`
+    pub p2p_tcp_port: u16,
+    /// The hostname other charon nodes reach this one at, `OBOL_P2P_EXTERNAL_HOSTNAME`
+    pub p2p_external_hostname: Option<String>,
+    /// `OBOL_LOG_LEVEL`, defaults to `info`
+    pub log_level: String,
+    /// The token to push metrics to Obol's monitoring with, `OBOL_MONITORING_TOKEN`
+    pub monitoring_token: Option<String>,
+}
+
+impl CharonEnv {
+    pub fn from_env() -> Result<Self> {
+        fn var(name: &str) -> Option<String> {
+            std::env::var(name)
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        }
+
+        fn list(name: &str) -> Vec<String> {
+            var(name)
+                .map(|value| {
+                    value
+                        .split(',')
+                        .map(|item| item.trim().to_string())
+                        .filter(|item| !item.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+
+        let p2p_tcp_port = match var("OBOL_P2P_TCP_PORT") {
+            Some(port) => port
+                .parse()
+                .map_err(|e| eyre!("Invalid OBOL_P2P_TCP_PORT `{port}`: {e}"))?,
+            None => 3610,
+        };
+
+        let env = Self {
+            network: Network::from_env()?,
+            beacon_node_endpoints: list("OBOL_BEACON_NODE_ENDPOINTS"),
+            validator_client: validator_client(
+                var("OBOL_VALIDATOR_CLIENT")
+                    .as_deref()
+                    .unwrap_or("lighthouse"),
+            )?,
+            execution_endpoint: var("OBOL_EXECUTION_ENDPOINT"),
+            mev_boost_relays: list("OBOL_MEV_BOOST_RELAYS"),
+            p2p_tcp_port,
+            p2p_external_hostname: var("OBOL_P2P_EXTERNAL_HOSTNAME"),
+            log_level: var("OBOL_LOG_LEVEL").unwrap_or_else(|| String::from("info")),
+            monitoring_token: var("OBOL_MONITORING_TOKEN"),
+        };
+
+        env.validate()?;
+        Ok(env)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        self.network.validate()?;
+
+        if self.beacon_node_endpoints.is_empty() {
+            bail!(
+                "At least one beacon node endpoint is required for {}, set OBOL_BEACON_NODE_ENDPOINTS",
+                self.network
+            );
+        }
+
+        for endpoint in &self.beacon_node_endpoints {
+            check_url("beacon node endpoint", endpoint)?;
+        }
+
+        if let Some(endpoint) = &self.execution_endpoint {
+            check_url("execution endpoint", endpoint)?;
+        }
+
+        for relay in &self.mev_boost_relays {
+            check_url("MEV-boost relay", relay)?;
+        }
+
+        if self.p2p_tcp_port == 0 {
+            bail!("The charon P2P TCP port must not be 0");
+        }
+
+        if let Some(hostname) = &self.p2p_external_hostname {
+            check_value("P2P external hostname", hostname)?;
+        }
+
+        if !LOG_LEVELS.contains(&self.log_level.as_str()) {
+            bail!(
+                "Unsupported log level `{}`, expected one of {}",
+                self.log_level,
+                LOG_LEVELS.join(", ")
+            );
+        }
+
+        if let Some(token) = &self.monitoring_token {
+            check_value("monitoring token", token)?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether charon and the validator client propose through the builder API
+    pub fn builder_api(&self) -> bool {
+        !self.mev_boost_relays.is_empty()
+    }
+}
+
+fn check_url(what: &str, url: &str) -> Result<()> {
+    check_value(what, url)?;
+
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        bail!("Invalid {what} `{url}`, expected an http(s) URL");
+    }
+
+    Ok(())
+}
+
+/// Values are passed to the containers' environment and arguments as-is, so they can't contain
+/// whitespace or quotes
+fn check_value(what: &str, value: &str) -> Result<()> {
+    if value
+        .chars()
+        .any(|c| c.is_whitespace() || c == '"' || c == '\'' || c == '#')
+    {
+        bail!("Invalid {what} `{value}`");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env() -> CharonEnv {
+        CharonEnv {
+            network: Network::Holesky,
+            beacon_node_endpoints: vec![String::from("http://beacon:5052")],
+            validator_client: validator_client("lighthouse").unwrap(),
+            execution_endpoint: Some(String::from("https://execution:8545")),
+            mev_boost_relays: Vec::new(),
+            p2p_tcp_port: 3610,
+            p2p_external_hostname: Some(String::from("node.example.com")),
+            log_level: String::from("info"),
+            monitoring_token: None,
+        }
+    }
+
+    #[test]
+    fn from_env_reads_the_configuration() {
+        // The only test reading these variables, so it can't race with another
+        std::env::set_var(
+            "OBOL_BEACON_NODE_ENDPOINTS",
+            " http://a:5052, ,https://b:5052 ",
+        );
+        std::env::set_var("OBOL_MEV_BOOST_RELAYS", "https://relay");
+        std::env::set_var("OBOL_VALIDATOR_CLIENT", "teku");
+        std::env::set_var("OBOL_P2P_TCP_PORT", "3611");
+        std::env::set_var("OBOL_LOG_LEVEL", "");
+
+        let env = CharonEnv::from_env().unwrap();
+        assert_eq!(
+            env.beacon_node_endpoints,
+            ["http://a:5052", "https://b:5052"]
+        );
+        assert_eq!(env.validator_client.name(), "teku");
+        assert_eq!(env.p2p_tcp_port, 3611);
+        assert_eq!(env.log_level, "info");
+        assert!(env.builder_api());
+
+        std::env::set_var("OBOL_P2P_TCP_PORT", "port");
+        assert!(CharonEnv::from_env().is_err());
+        std::env::set_var("OBOL_P2P_TCP_PORT", "3611");
+
+        std::env::set_var("OBOL_VALIDATOR_CLIENT", "vouch");
+        assert!(CharonEnv::from_env().is_err());
+        std::env::remove_var("OBOL_VALIDATOR_CLIENT");
+
+        std::env::set_var("OBOL_BEACON_NODE_ENDPOINTS", " , ");
+        assert!(CharonEnv::from_env().is_err());
+
+        for name in [
+            "OBOL_BEACON_NODE_ENDPOINTS",
+            "OBOL_MEV_BOOST_RELAYS",
+            "OBOL_P2P_TCP_PORT",
+            "OBOL_LOG_LEVEL",
+        ] {
+            std::env::remove_var(name);
+        }
+    }
+
+    #[test]
+    fn validate_accepts_a_valid_configuration() {
+        env().validate().unwrap();
+        assert!(!env().builder_api());
+    }
+
+    #[test]
+    fn validate_rejects_invalid_values() {
+        let invalid = [
+            CharonEnv {
+                beacon_node_endpoints: Vec::new(),
+                ..env()
+            },
+            CharonEnv {
+                beacon_node_endpoints: vec![String::from("beacon:5052")],
+                ..env()
+            },
+            CharonEnv {
+                execution_endpoint: Some(String::from("ws://execution:8546")),
+                ..env()
+            },
+            CharonEnv {
+                mev_boost_relays: vec![String::from("https://relay one")],
+                ..env()
+            },
+            CharonEnv {
+                p2p_tcp_port: 0,
+                ..env()
+            },
+            CharonEnv {
+                p2p_external_hostname: Some(String::from("node\"example")),
+                ..env()
+            },
+            CharonEnv {
+                log_level: String::from("trace"),
+                ..env()
+            },
+            CharonEnv {
+                monitoring_token: Some(String::from("token # comment")),
+                ..env()
+            },
+            CharonEnv {
+                network: Network::Devnet {
+                    name: String::from("mainnet"),
+                    genesis_fork_version: String::from("0x10000000"),
+                    chain_id: 1337,
+                    genesis_timestamp: 0,
+                    deposit_contract: format!("0x{}", "42".repeat(20)),
+                },
+                ..env()
+            },
+        ];
+
+        for env in invalid {
+            assert!(env.validate().is_err(), "{env:?}");
+        }
+    }
+}
@@ -1,9 +1,38 @@
+mod archive;
+mod backup;
+mod beacon;
+mod chain;
+mod cluster;
+mod deposit;
+mod env;
 mod network;
+mod node;
 mod operator;
+mod request;
+mod service;
+mod slashing;
+mod stack;
+mod state;
+mod validator;
 
+pub use archive::*;
+pub use backup::*;
+pub use beacon::*;
+pub use chain::*;
+pub use cluster::*;
+pub use deposit::*;
+pub use env::*;
 pub use network::*;
+pub use node::*;
 pub use operator::*;
+pub use request::*;
+pub use service::*;
+pub use slashing::*;
+pub use stack::*;
+pub use state::*;
+pub use validator::*;
 
+use color_eyre::eyre::{bail, eyre};
 use gadget_sdk as sdk;
 use sdk::config::StdGadgetConfiguration;
 use sdk::ctx::{ServicesContext, TangleClientContext};
@@ -11,30 +40,348 @@ use sdk::event_listener::tangle::jobs::{services_post_processor, services_pre_pr
 use sdk::event_listener::tangle::TangleEventListener;
 use sdk::job;
 use sdk::network::gossip::GossipHandle;
+use sdk::request_hook;
 use sdk::tangle_subxt::tangle_testnet_runtime::api::services::events::JobCalled;
-use std::convert::Infallible;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 #[derive(TangleClientContext, ServicesContext)]
 pub struct ObolContext {
     pub dv_operator: Operator,
+    pub charon_env: CharonEnv,
     pub network: GossipHandle,
+    pub timeouts: ExchangeTimeouts,
+    pub backups: BackupConfig,
     #[config]
     pub env: StdGadgetConfiguration,
 }
 
+#[derive(Debug, Clone)]
 pub struct DkgConfig {
     pub name: String,
+    pub network: Network,
     pub validator_count: u32,
+    /// The ENRs of the other operators
     pub enrs: Vec<String>,
-    // TODO: These should be request arguments
-    pub todo_bogus_fee_recipient_address: String,
-    pub todo_bogus_withdraw_address: String,
+    /// One fee recipient per validator
+    pub fee_recipients: Vec<String>,
+    /// One withdrawal address per validator
+    pub withdrawal_addresses: Vec<String>,
 }
 
+impl DkgConfig {
+    /// Create a config from the service request arguments, with no ENRs yet
+    pub fn new(
+        name: String,
+        network: Network,
+        validator_count: u32,
+        fee_recipients: Vec<String>,
+        withdrawal_addresses: Vec<String>,
+    ) -> color_eyre::Result<Self> {
+        if name.is_empty() {
+            bail!("Cluster name must not be empty");
+        }
+
+        if validator_count == 0 {
+            bail!("At least one validator is required");
+        }
+
+        if fee_recipients.len() != validator_count as usize {
+            bail!(
+                "Expected {validator_count} fee recipients, got {}",
+                fee_recipients.len()
+            );
+        }
+
+        if withdrawal_addresses.len() != validator_count as usize {
+            bail!(
+                "Expected {validator_count} withdrawal addresses, got {}",
+                withdrawal_addresses.len()
+            );
+        }
+
+        for address in &fee_recipients {
+            check_hex("fee recipient address", address, 20)?;
+        }
+
+        for address in &withdrawal_addresses {
+            check_hex("withdrawal address", address, 20)?;
+        }
+
+        Ok(Self {
+            name,
+            network,
+            validator_count,
+            enrs: Vec::new(),
+            fee_recipients,
+            withdrawal_addresses,
+        })
+    }
+}
+
+/// The cluster to create, supplied by the service requester
+///
+/// These are validated and stored by `ObolDvtBlueprint.onRequest`, see [`fetch_requested_params`].
+/// The hook parameters are ordered by name in the blueprint metadata, so keep them sorted here.
+#[request_hook]
+pub fn on_request(
+    cluster_name: String,
+    fee_recipients: Vec<String>,
+    validator_count: u32,
+    withdrawal_addresses: Vec<String>,
+);
+
+/// Create the distributed validator cluster for this service
+///
+/// Exchanges ENRs between the operators, creates and distributes the DKG config, performs the DKG
+/// ceremony and, if `start_validator` is set, starts the validator. The result is the cluster lock
+/// hash, followed by the public key of each validator.
+///
+/// If the DKG ceremony fails on any operator, it is retried by all of them, see [`perform_dkg`].
+/// When every attempt fails, the job fails with the last attempt's [`DkgError`].
 #[job(
     id = 0,
-    params(a),
+    params(start_validator),
+    result(_),
+    event_listener(
+        listener = TangleEventListener<Arc<ObolContext>, JobCalled>,
+        pre_processor = services_pre_processor,
+        post_processor = services_post_processor,
+    )
+)]
+pub async fn create_cluster(
+    ctx: Arc<ObolContext>,
+    start_validator: bool,
+) -> color_eyre::Result<Vec<String>> {
+    let state = ctx.dv_operator.state().await;
+    if state >= ClusterState::DkgDone {
+        tracing::info!("Cluster already created ({state:?}), skipping DKG");
+    } else {
+        let client = ctx.tangle_client().await?;
+        let operators = ServiceOperators::fetch(&ctx, &client).await?;
+        let dkg_config = fetch_requested_params(&ctx, &client).await?;
+
+        let session = Session {
+            service_id: ctx
+                .env
+                .service_id()
+                .ok_or_else(|| eyre!("No service ID configured"))?,
+            call_id: CREATE_CLUSTER_ACTIVE_CALL_ID.load(Ordering::Relaxed),
+            attempt: 0,
+        };
+
+        let enrs = wait_for_operators(&ctx, &operators, session).await?;
+        ctx.dv_operator
+            .advance_state(ClusterState::AwaitingDefinition)
+            .await?;
+        let dkg_config = DkgConfig {
+            enrs: enrs.into_values().collect(),
+            ..dkg_config
+        };
+        exchange_dkg_config(&ctx, &operators, session, &dkg_config).await?;
+
+        if let Err(e) = perform_dkg(&ctx, &operators, session).await {
+            if let Some(dkg_error) = e.downcast_ref::<DkgError>() {
+                tracing::error!(
+                    logs = %dkg_error.logs,
+                    partial_artifacts = ?dkg_error.partial_artifacts,
+                    "Giving up on the DKG ceremony"
+                );
+            }
+
+            return Err(e);
+        }
+
+        ctx.dv_operator.advance_state(ClusterState::DkgDone).await?;
+    }
+
+    if start_validator {
+        ctx.dv_operator.start_validator().await?;
+    }
+
+    let lock = ctx.dv_operator.fetch_cluster_lock().await?;
+    let mut result = vec![lock.lock_hash.clone()];
+    result.extend(lock.validator_pubkeys().map(String::from));
+
+    Ok(result)
+}
+
+/// How many times the operators attempt the DKG ceremony before giving up
+const MAX_DKG_ATTEMPTS: u32 = 3;
+
+/// Perform the DKG ceremony together with the other operators
+///
+/// After each attempt the operators share their outcome, and if any of them failed, they all
+/// discard their output and try again, up to [`MAX_DKG_ATTEMPTS`] times. A [`DkgError`] is returned
+/// if the last attempt fails.
+async fn perform_dkg(
+    ctx: &ObolContext,
+    operators: &ServiceOperators,
+    session: Session,
+) -> color_eyre::Result<()> {
+    let threshold = ctx.dv_operator.fetch_dkg_config().await?.threshold;
+
+    let mut attempt = 1;
+    loop {
+        tracing::info!("DKG ceremony attempt {attempt} of {MAX_DKG_ATTEMPTS}");
+
+        let result = ctx.dv_operator.start_dkg_ceremony().await;
+        let lock_hash = match &result {
+            Ok(()) => Some(ctx.dv_operator.fetch_cluster_lock().await?.lock_hash),
+            Err(e) => {
+                tracing::error!("DKG ceremony attempt {attempt} failed: {e}");
+                None
+            }
+        };
+
+        let session = Session { attempt, ..session };
+        let outcomes = exchange_dkg_outcomes(ctx, operators, session, lock_hash.as_deref()).await?;
+
+        let failed = outcomes
+            .iter()
+            .filter(|(_, hash)| hash.is_none())
+            .map(|(user_id, _)| *user_id)
+            .collect::<Vec<_>>();
+
+        let error = match (result, lock_hash) {
+            (Ok(()), Some(lock_hash)) if failed.is_empty() => {
+                return verify_cluster_lock(&outcomes, operators, threshold as usize, &lock_hash);
+            }
+            (Err(e), _) => e,
+            (Ok(()), _) => {
+                // Our cluster lock is useless without the other operators' key shares
+                let partial_artifacts = ctx.dv_operator.remove_dkg_artifacts().await?;
+                DkgError {
+                    failure: DkgFailure::OtherOperators(failed),
+                    logs: String::new(),
+                    partial_artifacts,
+                }
+                .into()
+            }
+        };
+
+        if attempt == MAX_DKG_ATTEMPTS {
+            return Err(error);
+        }
+
+        tracing::warn!("Retrying the DKG ceremony");
+        attempt += 1;
+    }
+}
+
+/// Get the deposit data for the cluster's validators
+///
+/// `pubkey` selects a single validator, or [`ALL_VALIDATORS`] for every validator in the cluster.
+/// The result is one `deposit-data.json` entry per validator, as JSON. The deposit data is only
+/// available once the cluster has been created, see [`create_cluster`].
+#[job(
+    id = 1,
+    params(pubkey),
+    result(_),
+    event_listener(
+        listener = TangleEventListener<Arc<ObolContext>, JobCalled>,
+        pre_processor = services_pre_processor,
+        post_processor = services_post_processor,
+    )
+)]
+pub async fn deposit_data(
+    ctx: Arc<ObolContext>,
+    pubkey: String,
+) -> color_eyre::Result<Vec<String>> {
+    let network = &ctx.charon_env.network;
+    let deposits = ctx
+        .dv_operator
+        .fetch_deposit_data(network)
+        .await?
+        .into_iter()
+        .filter(|deposit| deposit.matches(&pubkey))
+        .collect::<Vec<_>>();
+
+    if deposits.is_empty() {
+        bail!("No deposit data found for validator `{pubkey}`");
+    }
+
+    tracing::info!(
+        "Deposits are made to the {network} deposit contract {}",
+        network.deposit_contract()
+    );
+
+    let deposits = deposits
+        .iter()
+        .map(serde_json::to_string)
+        .collect::<Result<_, _>>()?;
+    Ok(deposits)
+}
+
+/// Voluntarily exit the cluster's validators
+///
+/// `pubkey` selects a single validator, or [`ALL_VALIDATORS`] for every validator in the cluster.
+/// Each operator signs its partial exit and publishes it to the Obol API, then broadcasts the full
+/// exit once enough operators have signed. The result is the public keys of the validators whose
+/// exits were broadcast.
+///
+/// The node only moves to [`ClusterState::Exiting`] once the exits of every validator are
+/// broadcast, the validator keeps running for the others.
+#[job(
+    id = 2,
+    params(pubkey),
+    result(_),
+    event_listener(
+        listener = TangleEventListener<Arc<ObolContext>, JobCalled>,
+        pre_processor = services_pre_processor,
+        post_processor = services_post_processor,
+    )
+)]
+pub async fn exit_validators(
+    ctx: Arc<ObolContext>,
+    pubkey: String,
+) -> color_eyre::Result<Vec<String>> {
+    if ctx.dv_operator.state().await < ClusterState::DkgDone {
+        bail!("The cluster hasn't been created yet");
+    }
+
+    let lock = ctx.dv_operator.fetch_cluster_lock().await?;
+    let pubkeys = lock
+        .validator_pubkeys()
+        .filter(|validator| {
+            pubkey == ALL_VALIDATORS
+                || validator
+                    .strip_prefix("0x")
+                    .unwrap_or(validator)
+                    .eq_ignore_ascii_case(pubkey.strip_prefix("0x").unwrap_or(&pubkey))
+        })
+        .map(String::from)
+        .collect::<Vec<_>>();
+
+    if pubkeys.is_empty() {
+        bail!("Validator `{pubkey}` is not part of the cluster");
+    }
+
+    let selection = if pubkey == ALL_VALIDATORS {
+        ALL_VALIDATORS
+    } else {
+        &pubkeys[0]
+    };
+    ctx.dv_operator.sign_exit(selection).await?;
+    ctx.dv_operator.broadcast_exit(selection).await?;
+
+    // The validators keep their duties until they reach their exit epoch
+    if pubkeys.len() == lock.distributed_validators.len() {
+        ctx.dv_operator.advance_state(ClusterState::Exiting).await?;
+    }
+
+    Ok(pubkeys)
+}
+
+/// Tear down the node once the cluster's validators have exited
+///
+/// Stops the validator, then archives `.charon` and the validator client's data, including its
+/// slashing protection DB, encrypted with `OBOL_ARCHIVE_PASSPHRASE`. The node is only removed once
+/// the archive is written. Unless `force` is set, every validator must have exited on the beacon
+/// chain. The result is the path of the archive.
+#[job(
+    id = 3,
+    params(force),
     result(_),
     event_listener(
         listener = TangleEventListener<Arc<ObolContext>, JobCalled>,
@@ -42,6 +389,151 @@ pub struct DkgConfig {
         post_processor = services_post_processor,
     )
 )]
-pub fn update(ctx: Arc<ObolContext>, a: u32) -> Result<u32, Infallible> {
-    Ok(0)
+pub async fn teardown(ctx: Arc<ObolContext>, force: bool) -> color_eyre::Result<String> {
+    if ctx.dv_operator.has_cluster_lock() {
+        let lock = ctx.dv_operator.fetch_cluster_lock().await?;
+        let pubkeys = lock.validator_pubkeys().collect::<Vec<_>>();
+        let statuses = validator_statuses(&ctx.charon_env.beacon_node_endpoints, &pubkeys).await;
+
+        let active = match &statuses {
+            Ok(statuses) => pubkeys
+                .iter()
+                .filter(|pubkey| {
+                    !statuses
+                        .get(&pubkey.to_lowercase())
+                        .is_some_and(|status| is_exited(status))
+                })
+                .copied()
+                .collect::<Vec<_>>(),
+            Err(_) => pubkeys.clone(),
+        };
+
+        if active.is_empty() {
+            ctx.dv_operator.advance_state(ClusterState::Exited).await?;
+        } else {
+            if !force {
+                if let Err(e) = statuses {
+                    return Err(e.wrap_err("Unable to verify the validators have exited"));
+                }
+                bail!(
+                    "Validators {} haven't exited, exit them first or force the teardown",
+                    active.join(", ")
+                );
+            }
+            tracing::warn!(
+                "Forcing teardown with validators that haven't exited: {}",
+                active.join(", ")
+            );
+        }
+    }
+
+    let archive = ctx.dv_operator.teardown().await?;
+    Ok(archive.display().to_string())
+}
+
+/// Verify the node's backups can be restored
+///
+/// `backup` is the file name of a backup in the backup dir, or [`ALL_BACKUPS`]. Each backup's
+/// checksum must match, and it must decrypt with the operator's keys and hold the ENR private key.
+/// Backups encrypted to `OBOL_BACKUP_RECIPIENT` can't be decrypted without `OBOL_BACKUP_IDENTITY`,
+/// in which case only their checksum and header are checked. The result is the names of the
+/// verified backups.
+#[job(
+    id = 4,
+    params(backup),
+    result(_),
+    event_listener(
+        listener = TangleEventListener<Arc<ObolContext>, JobCalled>,
+        pre_processor = services_pre_processor,
+        post_processor = services_post_processor,
+    )
+)]
+pub async fn verify_backups(
+    ctx: Arc<ObolContext>,
+    backup: String,
+) -> color_eyre::Result<Vec<String>> {
+    let backups = ctx.backups.backups().await?;
+    let selected = backups
+        .iter()
+        .filter(|path| {
+            backup == ALL_BACKUPS || path.file_name().is_some_and(|name| name == backup.as_str())
+        })
+        .collect::<Vec<_>>();
+
+    if selected.is_empty() {
+        bail!("No backup `{backup}` in {}", ctx.backups.dir.display());
+    }
+
+    let mut verified = Vec::new();
+    let mut failures = Vec::new();
+    for path in selected {
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        match verify_backup(path, ctx.dv_operator.archive_keys()).await {
+            Ok(Some(files)) => {
+                tracing::info!("Verified backup {name}, {} files", files.len());
+                verified.push(name);
+            }
+            Ok(None) => {
+                tracing::info!(
+                    "Verified the checksum and header of backup {name}, there's no key to decrypt it"
+                );
+                verified.push(name);
+            }
+            Err(e) => {
+                tracing::error!("Backup {name} failed verification: {e}");
+                failures.push(format!("{name}: {e}"));
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        bail!("Backups failed verification:\n{}", failures.join("\n"));
+    }
+
+    Ok(verified)
+}
+
+/// Import slashing protection for the cluster's validators, then start the validator
+///
+/// `interchange` is EIP-3076 interchange JSON, such as the export of the validator client on the
+/// operator's previous host. It's needed to start the validator on a host where its client has no
+/// slashing protection data, see [`Operator::start_validator`]. The result is the public keys of
+/// the validators whose slashing protection was imported.
+#[job(
+    id = 5,
+    params(interchange),
+    result(_),
+    event_listener(
+        listener = TangleEventListener<Arc<ObolContext>, JobCalled>,
+        pre_processor = services_pre_processor,
+        post_processor = services_post_processor,
+    )
+)]
+pub async fn import_slashing_protection(
+    ctx: Arc<ObolContext>,
+    interchange: String,
+) -> color_eyre::Result<Vec<String>> {
+    let state = ctx.dv_operator.state().await;
+    if state < ClusterState::DkgDone {
+        bail!("The cluster hasn't been created yet");
+    }
+    if state == ClusterState::Exited {
+        bail!("The cluster's validators have exited");
+    }
+
+    let protection = ctx
+        .dv_operator
+        .import_slashing_protection(interchange.as_bytes())
+        .await?;
+    ctx.dv_operator.start_validator().await?;
+
+    Ok(protection
+        .data
+        .into_iter()
+        .map(|validator| validator.pubkey)
+        .collect())
 }
@@ -1,9 +1,9 @@
 use color_eyre::Result;
 use gadget_sdk as sdk;
 use obol_dvt_blueprint as blueprint;
-use sdk::ctx::{ServicesContext, TangleClientContext};
+use sdk::ctx::TangleClientContext;
 use sdk::docker;
-use sdk::ext::subxt::tx::Signer;
+use sdk::keystore::BackendExt;
 use sdk::runners::tangle::TangleConfig;
 use sdk::runners::BlueprintRunner;
 use std::path::{Path, PathBuf};
@@ -30,11 +30,35 @@ async fn main() -> Result<()> {
     }
 
     let docker = docker::connect_to_docker(None).await?;
-    let dv_operator = blueprint::Operator::new(docker, data_dir.clone()).await?;
+    let charon_env = blueprint::CharonEnv::from_env()?;
+    let archive_keys = blueprint::ArchiveKeys::from_env(env.keystore()?.ecdsa_key()?.signer())?;
+    let backups = blueprint::BackupConfig::from_env(&data_dir)?;
+    let dv_operator =
+        blueprint::Operator::new(docker, data_dir.clone(), &charon_env, archive_keys).await?;
+    match dv_operator.state().await {
+        state if state < blueprint::ClusterState::DkgDone => {
+            tracing::info!(
+                "Cluster not created yet ({state:?}), waiting for a create_cluster call"
+            );
+        }
+        // Exiting validators keep their duties until they reach their exit epoch
+        blueprint::ClusterState::ValidatorRunning | blueprint::ClusterState::Exiting => {
+            tracing::info!("Restarting the validator");
+            // Keep serving jobs, the validator may be waiting for `import_slashing_protection`
+            if let Err(e) = dv_operator.start_validator().await {
+                tracing::error!("Failed to restart the validator: {e:?}");
+            }
+        }
+        _ => {}
+    }
+
     let network = blueprint::start_p2p_network(&env).await?;
 
-    let mut ctx = blueprint::ObolContext {
+    let ctx = blueprint::ObolContext {
         network,
+        charon_env,
+        timeouts: blueprint::ExchangeTimeouts::from_env()?,
+        backups: backups.clone(),
         dv_operator,
         env,
     };
@@ -42,28 +66,48 @@ async fn main() -> Result<()> {
     let client = ctx.tangle_client().await?;
     let signer = ctx.env.first_sr25519_signer()?;
 
-    let operators = ctx.current_service_operators(&client).await?;
-    let my_operator_position = operators
-        .iter()
-        .position(|op| op.0 == signer.account_id())
-        .expect("operator should be present for the service");
+    // Create the event handler from the job
+    tracing::info!("Starting the event watcher ...");
 
-    let leader = my_operator_position == 0;
+    let ctx = Arc::new(ctx);
+    tokio::spawn(blueprint::run_backups(Arc::clone(&ctx), backups));
 
-    if leader {
-        blueprint::request_all_enrs(&mut ctx, operators.len() - 1).await?;
-    } else {
-        blueprint::request_config(&ctx, my_operator_position).await?;
-    }
+    let create_cluster_job = blueprint::CreateClusterEventHandler {
+        ctx: Arc::clone(&ctx),
+        service_id: ctx.env.service_id().unwrap(),
+        signer: signer.clone(),
+        client: client.clone(),
+    };
 
-    ctx.dv_operator.start_dkg_ceremony().await?;
-    ctx.dv_operator.start_validator().await?;
+    let deposit_data_job = blueprint::DepositDataEventHandler {
+        ctx: Arc::clone(&ctx),
+        service_id: ctx.env.service_id().unwrap(),
+        signer: signer.clone(),
+        client: client.clone(),
+    };
 
-    // Create the event handler from the job
-    tracing::info!("Starting the event watcher ...");
+    let exit_validators_job = blueprint::ExitValidatorsEventHandler {
+        ctx: Arc::clone(&ctx),
+        service_id: ctx.env.service_id().unwrap(),
+        signer: signer.clone(),
+        client: client.clone(),
+    };
 
-    let ctx = Arc::new(ctx);
-    let update_job = blueprint::UpdateEventHandler {
+    let teardown_job = blueprint::TeardownEventHandler {
+        ctx: Arc::clone(&ctx),
+        service_id: ctx.env.service_id().unwrap(),
+        signer: signer.clone(),
+        client: client.clone(),
+    };
+
+    let verify_backups_job = blueprint::VerifyBackupsEventHandler {
+        ctx: Arc::clone(&ctx),
+        service_id: ctx.env.service_id().unwrap(),
+        signer: signer.clone(),
+        client: client.clone(),
+    };
+
+    let import_slashing_protection_job = blueprint::ImportSlashingProtectionEventHandler {
         ctx: Arc::clone(&ctx),
         service_id: ctx.env.service_id().unwrap(),
         signer: signer.clone(),
@@ -72,7 +116,12 @@ async fn main() -> Result<()> {
 
     let tangle_config = TangleConfig::default();
     BlueprintRunner::new(tangle_config, ctx.env.clone())
-        .job(update_job)
+        .job(create_cluster_job)
+        .job(deposit_data_job)
+        .job(exit_validators_job)
+        .job(teardown_job)
+        .job(verify_backups_job)
+        .job(import_slashing_protection_job)
         .run()
         .await?;
 
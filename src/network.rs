@@ -3,123 +3,75 @@
 //! |       Leader        |          |   Other Nodes (N)   |
 //! +---------------------+          +---------------------+
 //!         |                                 |
-//!         |<------ HereIAm -----------------| (1) Initial ping
+//!         |<------ HereIAm -----------------| (1) Joining the leader's term
 //!         |                                 |
-//!         |------- RequestEnr ------------->| (2) Broadcast, after all peers available
+//!         |------- DkgConfigGenerated ----->| (2) To each node, after all peers joined
 //!         |                                 |
-//!         |<------ SendEnr(String) ---------| (3) Response
+//!         |<------ DkgConfigReceived -------| (3) Acknowledgment, or
+//!         |<------ DkgConfigRejected(String)| (3) If the config doesn't match what the node expects
 //!         |                                 |
-//!         |------- EnrReceived ------------>| (4) Acknowledgment
+//!         |------- ExchangeEnd ------------>| (4) To each node, Final acknowledgment, or
+//!         |------- ExchangeAborted(String) >| (4) Broadcast, if a node rejected the DKG config
 //!         |                                 |
-//!         |------- DkgConfigGenerated ----->| (5) After all ENRs received
-//!         |                                 |
-//!         |<------ DkgConfigReceived -------| (6) Acknowledgment
-//!         |                                 |
-//!         |------- ExchangeEnd ------------>| (7) Broadcast, Final acknowledgment
+//!         |<------ ExchangeEndReceived -----| (5) Acknowledgment
 //! ```
+//!
+//! `HereIAm`, `DkgConfigGenerated` and `ExchangeEnd` are resent until the response that follows
+//! them arrives, and resent messages are recognised by their ID.
+//!
+//! Before the exchange, every node pings each other node until it answers with its ENR, see
+//! [`wait_for_operators`]. The leader creates the DKG config from those ENRs, and followers check
+//! it holds exactly the ENRs they received.
+//!
+//! Each node's `UserID` is its position among the service operators, and every message is signed
+//! with the ECDSA key the node registered on Tangle, see [`ServiceOperators`].
+//! Messages are scoped to a [`Session`], a single `create_cluster` call, so messages from other
+//! services or earlier calls are ignored.
+//!
+//! Every message also carries the term it was sent in. The leader of each term is picked
+//! round-robin from the operators, and a node that times out, waiting for its leader or for the
+//! other nodes as the leader, moves on to the next term, see [`exchange_dkg_config`].
+//!
+//! After each DKG ceremony attempt, every node broadcasts the hash of the `cluster-lock.json` it
+//! produced, or that the attempt failed, see [`exchange_dkg_outcomes`]. If any node failed, they
+//! all retry together.
 
-// TODO: Potential improvements
-
-// The flow is: service starts, operators share their ENRs, the config is generated, and then distributed.
-//
-// In that flow, what if:
-// * A peer drops out at any point
-// * A peer doesn't share its ENR
-// * The peers dont get the config from the leader
-//    * Could just go to the next operator, round-robin style
-//    * Did the leader not send it? Was there a network error?
-
-use super::{DkgConfig, ObolContext};
+use super::{
+    ClusterDefinition, ClusterState, DkgConfig, ObolContext, ServiceOperators, DKG_TIMEOUT,
+};
 use color_eyre::eyre::eyre;
 use color_eyre::{Report, Result};
 use gadget_sdk as sdk;
-use libp2p::swarm::{NetworkBehaviour, SwarmEvent};
-use libp2p::{identify, noise, ping, relay, tcp, yamux};
 use sdk::config::StdGadgetConfiguration;
-use sdk::ext::sp_core::Pair;
-use sdk::futures::StreamExt;
+use sdk::ext::sp_core::hashing::blake2_256;
+use sdk::ext::sp_core::{ecdsa, Pair};
 use sdk::keystore::BackendExt;
 use sdk::libp2p;
 use sdk::network::channels::UserID;
 use sdk::network::gossip::GossipHandle;
 use sdk::network::setup::NetworkConfig;
-use sdk::network::{IdentifierInfo, Network};
+use sdk::network::{IdentifierInfo, Network, ProtocolMessage};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::Instant;
 
-// TODO: For testing, want to ensure all peers are running
-async fn spin(env: &StdGadgetConfiguration, identity: libp2p::identity::Keypair) -> Result<()> {
-    tracing::info!("Spinning till all peers available");
-    #[derive(NetworkBehaviour)]
-    struct Behaviour {
-        relay: relay::Behaviour,
-        ping: ping::Behaviour,
-        identify: identify::Behaviour,
-    }
-
-    let mut swarm = libp2p::SwarmBuilder::with_existing_identity(identity)
-        .with_tokio()
-        .with_tcp(
-            tcp::Config::default(),
-            noise::Config::new,
-            yamux::Config::default,
-        )?
-        .with_quic()
-        .with_behaviour(|key| Behaviour {
-            relay: relay::Behaviour::new(key.public().to_peer_id(), Default::default()),
-            ping: ping::Behaviour::new(ping::Config::new()),
-            identify: identify::Behaviour::new(identify::Config::new(
-                "/TODO/0.0.1".to_string(),
-                key.public(),
-            )),
-        })?
-        .build();
-
-    swarm.listen_on(format!("/ip4/{}/tcp/{}", env.target_addr, env.target_port).parse()?)?;
-
-    // Connect to bootnodes
-    for addr in &env.bootnodes {
-        swarm.dial(addr.clone())?;
-    }
-
-    // Spin until all bootnodes are connected
-    let mut connected_bootnodes = 0;
-    let mut all_peers_discovered = false;
-
-    loop {
-        tracing::info!("Checking events");
-        match swarm.select_next_some().await {
-            SwarmEvent::ConnectionEstablished { peer_id, .. } => {
-                tracing::info!("Connected to {:?}", peer_id);
-                connected_bootnodes += 1;
-
-                if connected_bootnodes == env.bootnodes.len() {
-                    tracing::info!("All bootnodes connected!");
-                    all_peers_discovered = true;
-                    break;
-                }
-            }
-            SwarmEvent::ConnectionClosed { peer_id, .. } => {
-                if !all_peers_discovered {
-                    // TODO
-                    tracing::error!("{:?} dropped, how to handle?", peer_id);
-                }
-            }
-            e => {
-                tracing::error!("{e:?}");
-            }
-        }
-    }
-
-    tracing::info!("Done spinning");
+/// Domain separator for deriving the libp2p identity from the operator's ECDSA key
+const P2P_IDENTITY_CONTEXT: &[u8] = b"obol-dvt-blueprint/libp2p-identity";
 
-    Ok(())
+/// Derive the libp2p identity from the operator's ECDSA key in the keystore
+///
+/// This keeps the peer ID stable across restarts, so peers can recognise a reconnecting operator.
+fn p2p_identity(ecdsa: &ecdsa::Pair) -> Result<libp2p::identity::Keypair> {
+    let mut secret = blake2_256(&[P2P_IDENTITY_CONTEXT, &ecdsa.seed()].concat());
+    Ok(libp2p::identity::Keypair::ed25519_from_bytes(&mut secret)?)
 }
 
 pub async fn start_p2p_network(env: &StdGadgetConfiguration) -> Result<GossipHandle> {
     let ecdsa = env.keystore()?.ecdsa_key()?;
-    let identity = libp2p::identity::Keypair::generate_ed25519();
-
-    spin(&env, identity.clone()).await?;
+    let identity = p2p_identity(ecdsa.signer())?;
+    tracing::info!("Local peer ID: {}", identity.public().to_peer_id());
 
     let network_config = NetworkConfig::new_service_network(
         identity,
@@ -135,236 +87,1050 @@ pub async fn start_p2p_network(env: &StdGadgetConfiguration) -> Result<GossipHan
     Ok(handle)
 }
 
-pub async fn request_all_enrs(ctx: &mut ObolContext, expected_count: usize) -> Result<Vec<String>> {
-    let mut enrs = Vec::with_capacity(expected_count);
-
-    let my_ecdsa_key = ctx.env.keystore()?.ecdsa_key()?.public();
-
-    let _span = tracing::info_span!("leader", key = %my_ecdsa_key).entered();
-
-    // TODO ??
-    let my_user_id = 0;
-
-    let mut peers = 0;
-    let mut configs_received = 0;
-    while let Some(msg) = ctx.network.next_message().await {
-        let payload: Msg = sdk::network::deserialize(&msg.payload)?;
-
-        match payload {
-            Msg::HereIAm => {
-                tracing::info!("Received HereIAm from peer #{}", msg.sender.user_id);
-                peers += 1;
-
-                if peers == expected_count {
-                    tracing::info!("Requesting all ENRs");
-
-                    let enr_request = GossipHandle::build_protocol_message(
-                        IdentifierInfo {
-                            block_id: None,
-                            session_id: None,
-                            retry_id: None,
-                            task_id: None,
-                        },
-                        my_user_id,
-                        None, // Broadcast
-                        &Msg::RequestEnr,
-                        Some(my_ecdsa_key),
-                        None,
-                    );
+/// Wait until every other operator of the service answers over the gossip network, returning
+/// their ENRs
+///
+/// Each operator is pinged until it answers with a signed `Pong` carrying its ENR, which every
+/// [`Channel`] sends whenever it's pinged, so operators that are done waiting keep answering. This
+/// way every operator has the ENRs of all others, to check the DKG config against. This fails once
+/// the [`ExchangeTimeouts::connection`] deadline passes, naming the operators that didn't answer.
+/// An operator that disconnects after answering is caught by the deadlines of the exchange.
+#[tracing::instrument(skip_all)]
+pub async fn wait_for_operators(
+    ctx: &ObolContext,
+    operators: &ServiceOperators,
+    session: Session,
+) -> Result<BTreeMap<UserID, String>> {
+    let deadline = Instant::now() + ctx.timeouts.connection;
+    let mut channel = Channel::new(ctx, operators, session, 0)?;
+    let mut enrs = BTreeMap::new();
+
+    for peer in operators.peers() {
+        channel.send_until_acknowledged(peer, Msg::Ping).await?;
+    }
+
+    while channel.has_unacknowledged() {
+        let Some(received) = channel.recv_until(deadline).await? else {
+            return Err(eyre!(
+                "Timed out after {:?} waiting for operators to connect: {}",
+                ctx.timeouts.connection,
+                operators.describe(channel.unacknowledged())
+            ));
+        };
+
+        if let Msg::Pong(enr) = received.msg {
+            if !received.duplicate {
+                tracing::info!("Operator #{} connected", received.sender);
+            }
+            channel.acknowledge(received.sender, |msg| matches!(msg, Msg::Ping));
+            enrs.insert(received.sender, enr);
+        }
+    }
+
+    tracing::info!("All {} operators connected", operators.len());
+    Ok(enrs)
+}
+
+/// Deadlines for each phase of the ENR and config exchange
+///
+/// Each can be overridden with an environment variable, in seconds.
+#[derive(Debug, Clone)]
+pub struct ExchangeTimeouts {
+    /// For all operators to answer over the gossip network, `OBOL_CONNECTION_TIMEOUT`
+    pub connection: Duration,
+    /// For all operators to join the leader's term, `OBOL_DISCOVERY_TIMEOUT`
+    pub discovery: Duration,
+    /// For all operators to acknowledge the DKG config, `OBOL_CONFIG_DISTRIBUTION_TIMEOUT`
+    pub config_distribution: Duration,
+    /// For the leader to end the exchange after the DKG config is acknowledged, and for all
+    /// operators to acknowledge that, `OBOL_ACK_TIMEOUT`
+    ///
+    /// This should be at least `config_distribution`, as the leader waits for every operator.
+    pub ack: Duration,
+}
+
+impl Default for ExchangeTimeouts {
+    fn default() -> Self {
+        Self {
+            connection: Duration::from_secs(10 * 60),
+            discovery: Duration::from_secs(10 * 60),
+            config_distribution: Duration::from_secs(5 * 60),
+            ack: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+impl ExchangeTimeouts {
+    pub fn from_env() -> Result<Self> {
+        fn var(name: &str, default: Duration) -> Result<Duration> {
+            match std::env::var(name) {
+                Ok(secs) => secs
+                    .parse()
+                    .map(Duration::from_secs)
+                    .map_err(|e| eyre!("Invalid {name} `{secs}`: {e}")),
+                Err(_) => Ok(default),
+            }
+        }
+
+        let default = Self::default();
+        Ok(Self {
+            connection: var("OBOL_CONNECTION_TIMEOUT", default.connection)?,
+            discovery: var("OBOL_DISCOVERY_TIMEOUT", default.discovery)?,
+            config_distribution: var(
+                "OBOL_CONFIG_DISTRIBUTION_TIMEOUT",
+                default.config_distribution,
+            )?,
+            ack: var("OBOL_ACK_TIMEOUT", default.ack)?,
+        })
+    }
+
+    fn get(&self, phase: ExchangePhase) -> Duration {
+        match phase {
+            ExchangePhase::Discovery => self.discovery,
+            ExchangePhase::ConfigDistribution => self.config_distribution,
+            ExchangePhase::Ack => self.ack,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExchangePhase {
+    Discovery,
+    ConfigDistribution,
+    Ack,
+}
+
+impl fmt::Display for ExchangePhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExchangePhase::Discovery => write!(f, "discovery"),
+            ExchangePhase::ConfigDistribution => write!(f, "config distribution"),
+            ExchangePhase::Ack => write!(f, "acknowledgment"),
+        }
+    }
+}
+
+/// Tracks the current phase of the exchange and its deadline
+struct PhaseTimer<'a> {
+    timeouts: &'a ExchangeTimeouts,
+    phase: ExchangePhase,
+    timeout: Duration,
+    deadline: Instant,
+}
+
+impl<'a> PhaseTimer<'a> {
+    fn start(timeouts: &'a ExchangeTimeouts) -> Self {
+        Self::with_timeout(timeouts, ExchangePhase::Discovery, timeouts.discovery)
+    }
+
+    /// A follower waits for the leader's discovery and config distribution phases at once
+    fn await_config(timeouts: &'a ExchangeTimeouts) -> Self {
+        Self::with_timeout(
+            timeouts,
+            ExchangePhase::ConfigDistribution,
+            timeouts.discovery + timeouts.config_distribution,
+        )
+    }
+
+    fn with_timeout(
+        timeouts: &'a ExchangeTimeouts,
+        phase: ExchangePhase,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            timeouts,
+            phase,
+            timeout,
+            deadline: Instant::now() + timeout,
+        }
+    }
+
+    fn enter(&mut self, phase: ExchangePhase) {
+        tracing::debug!("Entering {phase} phase");
+        *self = Self::with_timeout(self.timeouts, phase, self.timeouts.get(phase));
+    }
+
+    fn expired(
+        &self,
+        operators: &ServiceOperators,
+        unresponsive: impl IntoIterator<Item = UserID>,
+    ) -> Report {
+        eyre!(
+            "The {} phase timed out after {:?}, no response from: {}",
+            self.phase,
+            self.timeout,
+            operators.describe(unresponsive)
+        )
+    }
+}
 
-                    ctx.network.send_message(enr_request).await?;
+/// How often a message is sent again, until it's acknowledged
+const RESEND_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How an exchange term ended
+enum TermOutcome {
+    /// The DKG config was distributed to every operator
+    Completed,
+    /// The leader stopped responding, or another operator started a later term
+    Superseded {
+        next_term: u32,
+        /// The operators that didn't respond in time, if the term timed out
+        unresponsive: Vec<UserID>,
+    },
+}
+
+/// Distribute the DKG config, electing a new leader if needed
+///
+/// `dkg_config` must hold the ENRs from [`wait_for_operators`], the leader creates the DKG config
+/// from them, and followers check it against them.
+///
+/// The leader of each term is the next operator in the service's order, starting with the first.
+/// If a peer's leader doesn't respond within the phase deadline, or a message from a later term
+/// arrives, the peer moves on to that term. A leader that doesn't hear from every peer in time
+/// steps down the same way, and follows the next term. Messages from earlier terms are ignored, so
+/// a stale leader can't interfere. Each operator leads at most one term.
+///
+/// Every operator has to take part in the term that completes, as the DKG config holds all of
+/// their ENRs, so this fails if one of them doesn't respond in any term, naming the operators that
+/// didn't respond.
+pub async fn exchange_dkg_config(
+    ctx: &ObolContext,
+    operators: &ServiceOperators,
+    session: Session,
+    dkg_config: &DkgConfig,
+) -> Result<()> {
+    let mut term = 0;
+    let mut unresponsive = BTreeSet::new();
+    loop {
+        let leader = operators.leader(term);
+        tracing::info!(
+            "Starting term {term}, led by {}",
+            operators.describe([leader])
+        );
+
+        let outcome = if leader == operators.my_user_id() {
+            distribute_config(ctx, operators, session, term, dkg_config).await?
+        } else {
+            request_config(ctx, operators, session, term, dkg_config).await?
+        };
+
+        let TermOutcome::Superseded {
+            next_term,
+            unresponsive: timed_out,
+        } = outcome
+        else {
+            return Ok(());
+        };
+
+        unresponsive.extend(timed_out);
+        if next_term as usize >= operators.len() {
+            let mut error = format!(
+                "No leader completed the exchange after {} terms",
+                operators.len()
+            );
+            if !unresponsive.is_empty() {
+                error += &format!(", no response from: {}", operators.describe(unresponsive));
+            }
+            return Err(Report::msg(error));
+        }
+
+        term = next_term;
+    }
+}
+
+/// Lead a term: once every other operator joined, distribute the DKG config created from the ENRs
+/// in `dkg_config`
+///
+/// Messages are sent to each peer until it responds. Each phase must complete within its
+/// [`ExchangeTimeouts`] deadline, otherwise the term is superseded, naming the operators that
+/// didn't respond.
+#[tracing::instrument(name = "leader", skip_all, fields(term, key))]
+async fn distribute_config(
+    ctx: &ObolContext,
+    operators: &ServiceOperators,
+    session: Session,
+    term: u32,
+    dkg_config: &DkgConfig,
+) -> Result<TermOutcome> {
+    let mut channel = Channel::new(ctx, operators, session, term)?;
+    let expected_count = operators.len() - 1;
+
+    tracing::Span::current()
+        .record("term", term)
+        .record("key", tracing::field::display(&channel.key.public()));
+
+    let mut timer = PhaseTimer::start(&ctx.timeouts);
+    let mut peers = BTreeSet::new();
+    let mut configs_received = BTreeSet::new();
+    let mut ends_received = BTreeSet::new();
+    loop {
+        let Some(received) = channel.recv_until(timer.deadline).await? else {
+            let responded = match timer.phase {
+                ExchangePhase::Discovery => peers,
+                ExchangePhase::ConfigDistribution => configs_received,
+                ExchangePhase::Ack => ends_received,
+            };
+            let unresponsive = operators
+                .peers()
+                .filter(|user_id| !responded.contains(user_id))
+                .collect::<Vec<_>>();
+            let error = timer.expired(operators, unresponsive.iter().copied());
+
+            if timer.phase == ExchangePhase::Ack {
+                // Everyone has the DKG config, so the exchange is done regardless
+                tracing::warn!("{error}");
+                break;
+            }
+
+            // The peers time out waiting for us too, and move on to the next leader
+            tracing::warn!("{error}, stepping down");
+            return Ok(TermOutcome::Superseded {
+                next_term: term + 1,
+                unresponsive,
+            });
+        };
+
+        let sender = received.sender;
+        if received.term > term {
+            tracing::warn!("Peer #{sender} is in term {}, stepping down", received.term);
+            return Ok(TermOutcome::Superseded {
+                next_term: received.term,
+                unresponsive: Vec::new(),
+            });
+        }
+
+        match received.msg {
+            Msg::HereIAm(state) => {
+                tracing::info!("Received HereIAm from peer #{sender} ({state:?})");
+
+                if peers.insert(sender)
+                    && timer.phase == ExchangePhase::Discovery
+                    && peers.len() == expected_count
+                {
+                    ctx.dv_operator.create_dkg_config(dkg_config).await?;
+                    let definition = Box::new(ctx.dv_operator.fetch_dkg_config().await?);
+
+                    tracing::info!("Sending DKG config to peers");
+                    for peer in operators.peers() {
+                        channel
+                            .send_until_acknowledged(
+                                peer,
+                                Msg::DkgConfigGenerated(definition.clone()),
+                            )
+                            .await?;
+                    }
+                    timer.enter(ExchangePhase::ConfigDistribution);
+                }
+            }
+            Msg::DkgConfigReceived => {
+                channel.acknowledge(sender, |msg| matches!(msg, Msg::DkgConfigGenerated(_)));
+
+                if configs_received.insert(sender) {
+                    tracing::info!("Peer #{sender} received the DKG config successfully");
+                }
+
+                if timer.phase == ExchangePhase::ConfigDistribution
+                    && configs_received.len() == expected_count
+                {
+                    tracing::info!("Ending the exchange");
+                    for peer in operators.peers() {
+                        channel
+                            .send_until_acknowledged(peer, Msg::ExchangeEnd)
+                            .await?;
+                    }
+                    timer.enter(ExchangePhase::Ack);
                 }
             }
-            Msg::SendEnr(enr) => {
-                tracing::info!("Received a new ENR from peer #{}", msg.sender.user_id);
-
-                enrs.push(enr);
-
-                let response = GossipHandle::build_protocol_message(
-                    IdentifierInfo {
-                        block_id: None,
-                        session_id: None,
-                        retry_id: None,
-                        task_id: None,
-                    },
-                    my_user_id,
-                    Some(msg.sender.user_id),
-                    &Msg::EnrReceived,
-                    Some(my_ecdsa_key),
-                    None,
+            Msg::DkgConfigRejected(reason) => {
+                tracing::error!("Peer #{sender} rejected the DKG config: {reason}");
+
+                let reason = format!(
+                    "Operator {} rejected the DKG config: {reason}",
+                    operators.describe([sender])
                 );
+                channel
+                    .send(None, &Msg::ExchangeAborted(reason.clone()))
+                    .await?;
+                return Err(Report::msg(reason));
+            }
+            Msg::ExchangeEndReceived => {
+                channel.acknowledge(sender, |msg| matches!(msg, Msg::ExchangeEnd));
+
+                ends_received.insert(sender);
+                if ends_received.len() == expected_count {
+                    break;
+                }
+            }
+            _ => continue,
+        }
+    }
+
+    Ok(TermOutcome::Completed)
+}
+
+/// Follow a term: join the leader's term, and receive the DKG config
+///
+/// `HereIAm` is sent until the DKG config arrives, in case the leader comes online later. The
+/// config must arrive within the leader's discovery and config distribution deadlines, and the
+/// leader must end the exchange within the ack deadline after that, otherwise the term is
+/// superseded.
+#[tracing::instrument(name = "peer", skip_all, fields(user_id = %operators.my_user_id(), term, key))]
+async fn request_config(
+    ctx: &ObolContext,
+    operators: &ServiceOperators,
+    session: Session,
+    term: u32,
+    dkg_config: &DkgConfig,
+) -> Result<TermOutcome> {
+    let mut channel = Channel::new(ctx, operators, session, term)?;
+    let leader = operators.leader(term);
+
+    tracing::Span::current()
+        .record("term", term)
+        .record("key", tracing::field::display(&channel.key.public()));
+
+    let mut timer = PhaseTimer::await_config(&ctx.timeouts);
+    channel
+        .send_until_acknowledged(leader, Msg::HereIAm(ctx.dv_operator.state().await))
+        .await?;
+    loop {
+        let Some(received) = channel.recv_until(timer.deadline).await? else {
+            tracing::warn!(
+                "{}, electing a new leader",
+                timer.expired(operators, [leader])
+            );
+            return Ok(TermOutcome::Superseded {
+                next_term: term + 1,
+                unresponsive: vec![leader],
+            });
+        };
+
+        let sender = received.sender;
+        if received.term > term {
+            tracing::info!("Peer #{sender} is in term {}, following", received.term);
+            return Ok(TermOutcome::Superseded {
+                next_term: received.term,
+                unresponsive: Vec::new(),
+            });
+        }
 
-                ctx.network.send_message(response).await?;
-
-                if enrs.len() == expected_count {
-                    let dkg_config = create_dkg_config(ctx, enrs.clone()).await?;
-
-                    tracing::info!("Broadcasting DKG config to peers");
-                    let broadcast = GossipHandle::build_protocol_message(
-                        IdentifierInfo {
-                            block_id: None,
-                            session_id: None,
-                            retry_id: None,
-                            task_id: None,
-                        },
-                        my_user_id,
-                        None,
-                        &Msg::DkgConfigGenerated(dkg_config.clone()),
-                        Some(my_ecdsa_key),
-                        None,
+        if sender != leader {
+            continue;
+        }
+
+        match received.msg {
+            Msg::DkgConfigGenerated(definition) => {
+                channel.acknowledge(leader, |msg| matches!(msg, Msg::HereIAm(_)));
+
+                if !received.duplicate {
+                    tracing::info!(
+                        name = %definition.name,
+                        operators = definition.operators.len(),
+                        threshold = definition.threshold,
+                        validators = definition.num_validators,
+                        "Received DKG config, verifying..."
                     );
 
-                    ctx.network.send_message(broadcast).await?;
+                    if let Err(e) =
+                        definition.verify(ctx.dv_operator.enr(), operators.len(), dkg_config)
+                    {
+                        let reason = e.to_string();
+                        tracing::error!("Rejecting DKG config: {reason}");
+
+                        channel
+                            .send(Some(leader), &Msg::DkgConfigRejected(reason.clone()))
+                            .await?;
+                        return Err(eyre!("Rejected DKG config from leader: {reason}"));
+                    }
+
+                    tracing::info!("DKG config verified, copying...");
+                    ctx.dv_operator.copy_in_dkg_config(&definition).await?;
+                    timer.enter(ExchangePhase::Ack);
                 }
+
+                channel.send(Some(leader), &Msg::DkgConfigReceived).await?;
             }
-            Msg::DkgConfigReceived => {
-                // TODO: And if they dont...?
-                tracing::info!(
-                    "Peer #{} received the DKG config successfully",
-                    msg.sender.user_id
+            Msg::ExchangeEnd => {
+                tracing::info!("Ending exchange by leader request...");
+                channel
+                    .send(Some(leader), &Msg::ExchangeEndReceived)
+                    .await?;
+                break;
+            }
+            Msg::ExchangeAborted(reason) => {
+                return Err(eyre!("Leader aborted the exchange: {reason}"));
+            }
+            _ => continue,
+        }
+    }
+
+    Ok(TermOutcome::Completed)
+}
+
+/// Share the outcome of a DKG ceremony attempt with the other operators
+///
+/// Every operator sends its cluster lock hash, or `None` if its attempt failed, to each other
+/// operator until acknowledged, and waits up to [`DKG_TIMEOUT`] for the outcomes of all operators.
+/// The attempt is identified by `session`, so outcomes of other attempts are dropped.
+#[tracing::instrument(skip_all, fields(user_id = %operators.my_user_id(), attempt = session.attempt))]
+pub async fn exchange_dkg_outcomes(
+    ctx: &ObolContext,
+    operators: &ServiceOperators,
+    session: Session,
+    lock_hash: Option<&str>,
+) -> Result<BTreeMap<UserID, Option<String>>> {
+    // Outcomes aren't tied to a leader, so the term doesn't matter
+    let mut channel = Channel::new(ctx, operators, session, 0)?;
+
+    let mut outcomes = BTreeMap::new();
+    outcomes.insert(operators.my_user_id(), lock_hash.map(String::from));
+
+    match lock_hash {
+        Some(hash) => tracing::info!("Sending cluster lock hash {hash}"),
+        None => tracing::info!("Sending DKG failure"),
+    }
+    let outcome = Msg::DkgOutcome(lock_hash.map(String::from));
+    for peer in operators.peers() {
+        channel
+            .send_until_acknowledged(peer, outcome.clone())
+            .await?;
+    }
+
+    let mut deadline = Instant::now() + DKG_TIMEOUT;
+    while outcomes.len() < operators.len() || channel.has_unacknowledged() {
+        let Some(received) = channel.recv_until(deadline).await? else {
+            if outcomes.len() == operators.len() {
+                tracing::warn!(
+                    "No acknowledgment of our DKG outcome from: {}",
+                    operators.describe(channel.unacknowledged())
                 );
+                break;
+            }
 
-                configs_received += 1;
-                if configs_received == expected_count {
-                    tracing::info!("Broadcasting exchange end to peers");
-                    let broadcast = GossipHandle::build_protocol_message(
-                        IdentifierInfo {
-                            block_id: None,
-                            session_id: None,
-                            retry_id: None,
-                            task_id: None,
-                        },
-                        my_user_id,
-                        None,
-                        &Msg::ExchangeEnd,
-                        Some(my_ecdsa_key),
-                        None,
-                    );
+            let missing = operators
+                .user_ids()
+                .filter(|user_id| !outcomes.contains_key(user_id));
+            return Err(eyre!(
+                "Timed out waiting for DKG outcomes from: {}",
+                operators.describe(missing)
+            ));
+        };
 
-                    ctx.network.send_message(broadcast).await?;
-                    break;
+        let sender = received.sender;
+        match received.msg {
+            Msg::DkgOutcome(lock_hash) => {
+                channel.send(Some(sender), &Msg::DkgOutcomeReceived).await?;
+
+                if outcomes.contains_key(&sender) {
+                    continue;
+                }
+
+                match &lock_hash {
+                    Some(hash) => tracing::info!("Peer #{sender} has cluster lock hash {hash}"),
+                    None => tracing::warn!("Peer #{sender} failed the DKG ceremony"),
+                }
+                outcomes.insert(sender, lock_hash);
+
+                if outcomes.len() == operators.len() {
+                    // Only wait a little longer for our outcome to be acknowledged
+                    deadline = deadline.min(Instant::now() + ctx.timeouts.ack);
                 }
             }
+            Msg::DkgOutcomeReceived => {
+                channel.acknowledge(sender, |msg| matches!(msg, Msg::DkgOutcome(_)));
+            }
             _ => continue,
         }
     }
 
-    if enrs.len() != expected_count {
-        return Err(Report::msg("Not all ENRs were acquired").into());
+    Ok(outcomes)
+}
+
+/// Check that the other operators ended up with the same cluster lock after the DKG ceremony
+///
+/// `outcomes` are the lock hashes shared through [`exchange_dkg_outcomes`]. This succeeds if at
+/// least `threshold` operators (including this one) agree with our lock hash, and fails naming the
+/// dissenting operators otherwise.
+pub fn verify_cluster_lock(
+    outcomes: &BTreeMap<UserID, Option<String>>,
+    operators: &ServiceOperators,
+    threshold: usize,
+    lock_hash: &str,
+) -> Result<()> {
+    let operator_count = operators.len();
+    let agreeing = operators
+        .user_ids()
+        .filter(|user_id| outcomes.get(user_id) == Some(&Some(lock_hash.to_string())))
+        .count();
+    let dissenters = operators
+        .user_ids()
+        .filter_map(|user_id| {
+            let operator = operators.describe([user_id]);
+            match outcomes.get(&user_id) {
+                Some(Some(hash)) if hash == lock_hash => None,
+                Some(Some(hash)) => Some(format!("{operator}: {hash}")),
+                Some(None) => Some(format!("{operator}: DKG failed")),
+                None => Some(format!("{operator}: no response")),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if agreeing < threshold {
+        return Err(eyre!(
+            "Only {agreeing} of {operator_count} operators agree on the cluster lock, {threshold} required. Dissenting: {}",
+            dissenters.join(", ")
+        ));
     }
 
-    Ok(enrs)
+    if agreeing < operator_count {
+        tracing::warn!(
+            "{agreeing} of {operator_count} operators agree on the cluster lock. Dissenting: {}",
+            dissenters.join(", ")
+        );
+    } else {
+        tracing::info!("All operators agree on the cluster lock");
+    }
+
+    Ok(())
 }
 
-async fn create_dkg_config(ctx: &mut ObolContext, enrs: Vec<String>) -> Result<String> {
-    let dkg_config = DkgConfig {
-        name: "Example".to_string(),
-        validator_count: 1,
-        enrs,
-        todo_bogus_fee_recipient_address: String::from(
-            "0x0000000000000000000000000000000000000000",
-        ),
-        todo_bogus_withdraw_address: String::from("0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359"),
-    };
-
-    ctx.dv_operator.create_dkg_config(Some(dkg_config)).await?;
-    let content = ctx.dv_operator.fetch_dkg_config().await?;
-    Ok(content)
+/// Signed protocol messages between the operators of the service
+///
+/// Every message is signed with the sender's ECDSA key, and only accepted if the signature matches
+/// the key its claimed sender registered on Tangle. Messages have an ID unique to their sender, so
+/// resent messages can be recognised. Messages of other protocol versions or sessions are dropped.
+struct Channel<'a> {
+    network: &'a GossipHandle,
+    operators: &'a ServiceOperators,
+    key: ecdsa::Pair,
+    /// This operator's ENR, sent to the operators that ping it
+    enr: &'a str,
+    session: Session,
+    /// The exchange term messages are sent in, earlier terms are dropped when receiving
+    term: u32,
+    next_id: u64,
+    /// The IDs of the messages received from each operator
+    seen: BTreeMap<UserID, HashSet<u64>>,
+    /// Messages that are resent until acknowledged, at most one per operator
+    unacknowledged: BTreeMap<UserID, Unacknowledged>,
 }
 
-pub async fn request_config(ctx: &ObolContext, my_operator_position: usize) -> Result<()> {
-    let my_ecdsa_key = ctx.env.keystore()?.ecdsa_key()?.public();
+struct Unacknowledged {
+    msg: Msg,
+    id: u64,
+    resends: u32,
+    resend_at: Instant,
+}
 
-    let _span =
-        tracing::info_span!("peer", user_id = %my_operator_position, key = %my_ecdsa_key).entered();
+/// A verified message, see [`Channel::recv_until`]
+struct Received {
+    sender: UserID,
+    term: u32,
+    msg: Msg,
+    /// Whether this is a resend of a message that was already received
+    duplicate: bool,
+}
 
-    // TODO ??
-    let my_user_id = my_operator_position as UserID;
-    let mut leader_user_id = 0;
+impl<'a> Channel<'a> {
+    fn new(
+        ctx: &'a ObolContext,
+        operators: &'a ServiceOperators,
+        session: Session,
+        term: u32,
+    ) -> Result<Self> {
+        let key = ctx.env.keystore()?.ecdsa_key()?.signer().clone();
 
-    let initial_ping = GossipHandle::build_protocol_message(
-        IdentifierInfo {
-            block_id: None,
-            session_id: None,
-            retry_id: None,
-            task_id: None,
-        },
-        my_user_id,
-        Some(leader_user_id),
-        &Msg::HereIAm,
-        Some(my_ecdsa_key),
-        None,
-    );
+        // Seeded from the clock, so IDs differ from those of a previous run
+        let next_id = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64);
 
-    ctx.network.send_message(initial_ping).await?;
-    while let Some(msg) = ctx.network.next_message().await {
-        let payload: Msg = sdk::network::deserialize(&msg.payload)?;
-
-        match payload {
-            Msg::DkgConfigGenerated(msg) => {
-                tracing::info!("Received DKG config, copying...");
-
-                ctx.dv_operator.copy_in_dkg_config(msg).await?;
-
-                let response = GossipHandle::build_protocol_message(
-                    IdentifierInfo {
-                        block_id: None,
-                        session_id: None,
-                        retry_id: None,
-                        task_id: None,
-                    },
-                    my_user_id,
-                    Some(leader_user_id),
-                    &Msg::DkgConfigReceived,
-                    Some(my_ecdsa_key),
-                    None,
-                );
+        Ok(Self {
+            network: &ctx.network,
+            operators,
+            key,
+            enr: ctx.dv_operator.enr(),
+            session,
+            term,
+            next_id,
+            seen: BTreeMap::new(),
+            unacknowledged: BTreeMap::new(),
+        })
+    }
+
+    /// Send `msg` once to the operator `to`, or to everyone if `None`
+    async fn send(&mut self, to: Option<UserID>, msg: &Msg) -> Result<()> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.transmit(to, msg, id, 0).await
+    }
+
+    /// Send `msg` to the operator `to`, and keep resending it until [`Self::acknowledge`]d
+    ///
+    /// This replaces any unacknowledged message to `to`.
+    async fn send_until_acknowledged(&mut self, to: UserID, msg: Msg) -> Result<()> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.transmit(Some(to), &msg, id, 0).await?;
+        self.unacknowledged.insert(
+            to,
+            Unacknowledged {
+                msg,
+                id,
+                resends: 0,
+                resend_at: Instant::now() + RESEND_INTERVAL,
+            },
+        );
+
+        Ok(())
+    }
 
-                ctx.network.send_message(response).await?;
+    /// Stop resending to `from`, if the unacknowledged message matches `request`
+    fn acknowledge(&mut self, from: UserID, request: impl Fn(&Msg) -> bool) {
+        if let Some(pending) = self.unacknowledged.get(&from) {
+            if request(&pending.msg) {
+                self.unacknowledged.remove(&from);
             }
-            Msg::RequestEnr => {
-                tracing::info!("Leader requested ENR, sending...");
-
-                leader_user_id = msg.sender.user_id;
-                let response = GossipHandle::build_protocol_message(
-                    IdentifierInfo {
-                        block_id: None,
-                        session_id: None,
-                        retry_id: None,
-                        task_id: None,
-                    },
-                    my_user_id,
-                    Some(leader_user_id),
-                    &Msg::SendEnr(ctx.dv_operator.enr().to_string()),
-                    Some(my_ecdsa_key),
-                    None,
-                );
-                ctx.network.send_message(response).await?;
+        }
+    }
+
+    fn has_unacknowledged(&self) -> bool {
+        !self.unacknowledged.is_empty()
+    }
+
+    /// The operators that haven't acknowledged our last message to them
+    fn unacknowledged(&self) -> impl Iterator<Item = UserID> + '_ {
+        self.unacknowledged.keys().copied()
+    }
+
+    async fn resend_due(&mut self) -> Result<()> {
+        let now = Instant::now();
+        let due = self
+            .unacknowledged
+            .iter_mut()
+            .filter(|(_, pending)| pending.resend_at <= now)
+            .map(|(to, pending)| {
+                pending.resends += 1;
+                pending.resend_at = now + RESEND_INTERVAL;
+                (*to, pending.msg.clone(), pending.id, pending.resends)
+            })
+            .collect::<Vec<_>>();
+
+        for (to, msg, id, resend) in due {
+            tracing::debug!("Resending message #{id} to peer #{to}");
+            self.transmit(Some(to), &msg, id, resend).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn transmit(&self, to: Option<UserID>, msg: &Msg, id: u64, resend: u32) -> Result<()> {
+        let from = self.operators.my_user_id();
+        let header = Header {
+            version: PROTOCOL_VERSION,
+            session: self.session,
+            term: self.term,
+            id,
+            resend,
+        };
+        let signature = self.key.sign(&signing_payload(from, to, &header, msg)?);
+
+        let message = GossipHandle::build_protocol_message(
+            self.session.identifier_info(),
+            from,
+            to,
+            &Envelope {
+                header,
+                msg: msg.clone(),
+                signature,
+            },
+            Some(self.key.public()),
+            None,
+        );
+
+        self.network.send_message(message).await?;
+        Ok(())
+    }
+
+    /// Receive the next message for this operator, or `None` once `deadline` passes
+    ///
+    /// Unacknowledged messages are resent while waiting, and pings are answered, see
+    /// [`wait_for_operators`]. Messages for other operators, from earlier terms, or that fail
+    /// verification are dropped. Fails if the network is closed.
+    async fn recv_until(&mut self, deadline: Instant) -> Result<Option<Received>> {
+        loop {
+            self.resend_due().await?;
+
+            let wake_at = self
+                .unacknowledged
+                .values()
+                .map(|pending| pending.resend_at)
+                .fold(deadline, Instant::min);
+
+            let message = match tokio::time::timeout_at(wake_at, self.network.next_message()).await
+            {
+                Ok(Some(message)) => message,
+                Ok(None) => return Err(eyre!("Network closed")),
+                Err(_) if Instant::now() >= deadline => return Ok(None),
+                Err(_) => continue,
+            };
+
+            if let Some(recipient) = &message.recipient {
+                if recipient.user_id != self.operators.my_user_id() {
+                    continue;
+                }
             }
-            Msg::EnrReceived => {
-                tracing::info!("Leader received my ENR");
+
+            if !self.session.matches(&message.identifier_info) {
+                tracing::trace!("Dropping message for session {}", message.identifier_info);
+                continue;
             }
-            Msg::ExchangeEnd => {
-                tracing::info!("Ending exchange by leader request...");
-                break;
+
+            let sender = message.sender.user_id;
+            let envelope = match self.verify(&message) {
+                Ok(envelope) => envelope,
+                Err(e) => {
+                    tracing::warn!("Dropping message from peer #{sender}: {e}");
+                    continue;
+                }
+            };
+
+            let header = envelope.header;
+            if header.session != self.session {
+                tracing::warn!("Dropping message from peer #{sender} for another session");
+                continue;
             }
-            _ => continue,
+
+            // Answered regardless of the term, the peer is still waiting for operators to connect
+            if let Msg::Ping = envelope.msg {
+                self.send(Some(sender), &Msg::Pong(self.enr.to_string()))
+                    .await?;
+                continue;
+            }
+
+            if header.term < self.term {
+                tracing::debug!(
+                    "Dropping message from peer #{sender} in stale term {}",
+                    header.term
+                );
+                continue;
+            }
+
+            let duplicate = !self.seen.entry(sender).or_default().insert(header.id);
+            return Ok(Some(Received {
+                sender,
+                term: header.term,
+                msg: envelope.msg,
+                duplicate,
+            }));
         }
     }
 
-    Ok(())
+    fn verify(&self, message: &ProtocolMessage) -> Result<Envelope> {
+        let sender = message.sender.user_id;
+        let operator = self
+            .operators
+            .get(sender)
+            .ok_or_else(|| eyre!("Not an operator of this service"))?;
+
+        let version = sdk::network::deserialize::<Versioned>(&message.payload)?
+            .header
+            .version;
+        if version != PROTOCOL_VERSION {
+            return Err(eyre!(
+                "Unsupported protocol version {version}, expected {PROTOCOL_VERSION}"
+            ));
+        }
+
+        let envelope: Envelope = sdk::network::deserialize(&message.payload)?;
+        let to = message
+            .recipient
+            .as_ref()
+            .map(|recipient| recipient.user_id);
+        let payload = signing_payload(sender, to, &envelope.header, &envelope.msg)?;
+        if !ecdsa::Pair::verify(&envelope.signature, payload, &operator.ecdsa_key) {
+            return Err(eyre!(
+                "Invalid signature, expected one from {}",
+                operator.ecdsa_key
+            ));
+        }
+
+        Ok(envelope)
+    }
+}
+
+/// The bytes signed for a message, binding it to its sender, recipient and header
+fn signing_payload(
+    from: UserID,
+    to: Option<UserID>,
+    header: &Header,
+    msg: &Msg,
+) -> Result<Vec<u8>> {
+    Ok(sdk::network::serialize(&(from, to, header, msg))?)
+}
+
+/// Bumped whenever [`Envelope`] or [`Msg`] change incompatibly
+const PROTOCOL_VERSION: u16 = 1;
+
+/// The protocol run a message belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub service_id: u64,
+    /// The `create_cluster` job call
+    pub call_id: u64,
+    /// The DKG ceremony attempt, `0` for the config exchange before it
+    pub attempt: u32,
+}
+
+impl Session {
+    fn identifier_info(&self) -> IdentifierInfo {
+        IdentifierInfo {
+            block_id: None,
+            session_id: Some(self.service_id),
+            task_id: Some(self.call_id),
+            retry_id: Some(u64::from(self.attempt)),
+        }
+    }
+
+    fn matches(&self, info: &IdentifierInfo) -> bool {
+        let expected = self.identifier_info();
+        info.session_id == expected.session_id
+            && info.task_id == expected.task_id
+            && info.retry_id == expected.retry_id
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Header {
+    version: u16,
+    session: Session,
+    term: u32,
+    id: u64,
+    /// How many times the message was resent, as the network drops payloads it has already seen
+    resend: u32,
 }
 
 #[derive(Serialize, Deserialize)]
-enum Msg {
-    HereIAm,
+struct Envelope {
+    header: Header,
+    msg: Msg,
+    signature: ecdsa::Signature,
+}
+
+/// Only the version of an [`Envelope`], so it can be checked before the rest is understood
+#[derive(Deserialize)]
+struct Versioned {
+    header: VersionedHeader,
+}
+
+#[derive(Deserialize)]
+struct VersionedHeader {
+    version: u16,
+}
 
-    RequestEnr,
-    SendEnr(String),
-    EnrReceived,
+#[derive(Clone, Serialize, Deserialize)]
+enum Msg {
+    /// Carries the sender's [`ClusterState`], so the leader knows where each operator is
+    HereIAm(ClusterState),
 
-    DkgConfigGenerated(String),
+    DkgConfigGenerated(#[serde(with = "crate::cluster::raw_json")] Box<ClusterDefinition>),
     DkgConfigReceived,
+    DkgConfigRejected(String),
 
     ExchangeEnd,
+    ExchangeEndReceived,
+    ExchangeAborted(String),
+
+    /// The cluster lock hash, or `None` if the attempt failed
+    DkgOutcome(Option<String>),
+    DkgOutcomeReceived,
+
+    /// Sent until answered, see [`wait_for_operators`]
+    Ping,
+    /// Carries the sender's ENR
+    Pong(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ServiceOperator;
+    use sdk::ext::subxt::utils::AccountId32;
+
+    const LOCK_HASH: &str = "0xaaaa";
+
+    fn operators(count: u8) -> ServiceOperators {
+        let operators = (0..count)
+            .map(|i| ServiceOperator {
+                account: AccountId32([i; 32]),
+                ecdsa_key: ecdsa::Public::from_raw([i; 33]),
+            })
+            .collect();
+        ServiceOperators::new(operators, 0)
+    }
+
+    fn outcomes(
+        hashes: impl IntoIterator<Item = (UserID, Option<&'static str>)>,
+    ) -> BTreeMap<UserID, Option<String>> {
+        hashes
+            .into_iter()
+            .map(|(user_id, hash)| (user_id, hash.map(String::from)))
+            .collect()
+    }
+
+    #[test]
+    fn accepts_agreeing_operators() {
+        let outcomes = outcomes((0..4).map(|user_id| (user_id, Some(LOCK_HASH))));
+        verify_cluster_lock(&outcomes, &operators(4), 3, LOCK_HASH).unwrap();
+    }
+
+    #[test]
+    fn rejects_mismatched_lock_hashes() {
+        let outcomes = outcomes([
+            (0, Some(LOCK_HASH)),
+            (1, Some(LOCK_HASH)),
+            (2, Some("0xbbbb")),
+            (3, None),
+        ]);
+
+        let error = verify_cluster_lock(&outcomes, &operators(4), 3, LOCK_HASH).unwrap_err();
+        let error = error.to_string();
+        assert!(error.contains("Only 2 of 4"), "{error}");
+        assert!(
+            error.contains("#2 (") && error.contains(": 0xbbbb"),
+            "{error}"
+        );
+        assert!(error.contains("DKG failed"), "{error}");
+
+        // Enough operators agree, the others are only warned about
+        verify_cluster_lock(&outcomes, &operators(4), 2, LOCK_HASH).unwrap();
+    }
+
+    #[test]
+    fn rejects_missing_operators() {
+        let outcomes = outcomes([(0, Some(LOCK_HASH)), (1, Some(LOCK_HASH))]);
+
+        let error = verify_cluster_lock(&outcomes, &operators(4), 3, LOCK_HASH).unwrap_err();
+        let error = error.to_string();
+        assert!(
+            error.contains("#2 (") && error.contains("no response"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn counts_each_operator_once() {
+        // A resent outcome, and one from an operator that isn't part of the service
+        let outcomes = outcomes([
+            (0, Some(LOCK_HASH)),
+            (1, Some(LOCK_HASH)),
+            (1, Some(LOCK_HASH)),
+            (4, Some(LOCK_HASH)),
+        ]);
+
+        let error = verify_cluster_lock(&outcomes, &operators(4), 3, LOCK_HASH).unwrap_err();
+        assert!(error.to_string().contains("Only 2 of 4"), "{error}");
+    }
 }
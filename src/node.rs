@@ -0,0 +1,62 @@
+use color_eyre::Result;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+/// A file of the distributed validator node, embedded in the binary
+struct NodeFile {
+    path: &'static str,
+    content: &'static str,
+    executable: bool,
+}
+
+const NODE_FILES: &[NodeFile] = &[
+    NodeFile {
+        path: "lighthouse/run.sh",
+        content: include_str!("../node/lighthouse/run.sh"),
+        executable: true,
+    },
+    NodeFile {
+        path: "teku/run.sh",
+        content: include_str!("../node/teku/run.sh"),
+        executable: true,
+    },
+    NodeFile {
+        path: "nimbus/run.sh",
+        content: include_str!("../node/nimbus/run.sh"),
+        executable: true,
+    },
+    NodeFile {
+        path: "lodestar/run.sh",
+        content: include_str!("../node/lodestar/run.sh"),
+        executable: true,
+    },
+    NodeFile {
+        path: "prometheus/prometheus.yml.example",
+        content: include_str!("../node/prometheus/prometheus.yml.example"),
+        executable: false,
+    },
+    NodeFile {
+        path: "prometheus/run.sh",
+        content: include_str!("../node/prometheus/run.sh"),
+        executable: true,
+    },
+];
+
+/// Render the distributed validator node into `node_dir`
+///
+/// The files are rewritten on every start, so the node always matches this blueprint version.
+pub async fn render_node(node_dir: &Path) -> Result<()> {
+    for file in NODE_FILES {
+        let path = node_dir.join(file.path);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        tokio::fs::write(&path, file.content).await?;
+        if file.executable {
+            tokio::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).await?;
+        }
+    }
+
+    Ok(())
+}
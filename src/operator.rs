@@ -1,70 +1,152 @@
-use crate::DkgConfig;
+use crate::{
+    container_logs, create_archive, extract_archive, list_archive, render_node,
+    restore_archive_path, verify_checksum, ArchiveKeys, BackupConfig, CharonEnv, ClusterDefinition,
+    ClusterLock, ClusterState, DepositData, DkgConfig, Interchange, Network, SlashingProtection,
+    ValidatorStack, ALL_VALIDATORS, BACKUP_PATHS, INTERCHANGE_FILE, STATE_FILE,
+};
 use bollard::Docker;
+use color_eyre::eyre::eyre;
 use color_eyre::{Report, Result};
 use gadget_sdk as sdk;
 use gadget_sdk::docker::bollard::container::{LogOutput, LogsOptions};
 use sdk::docker::{bollard, Container};
 use sdk::ext::subxt::ext::futures::StreamExt;
+use sdk::network::channels::UserID;
+use std::fmt;
+use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
-use std::process::Command;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
 
 pub struct Operator {
     data_dir: PathBuf,
     enr: String,
+    env: CharonEnv,
+    keys: ArchiveKeys,
+    state_path: PathBuf,
+    state: Mutex<ClusterState>,
     docker: Arc<Docker>,
     span: tracing::Span,
 }
 
-const IMAGE: &str = "obolnetwork/charon:v1.1.1";
-const CHARON_DATA: &str = "/opt/charon";
+pub(crate) const IMAGE: &str = "obolnetwork/charon:v1.1.1";
+pub(crate) const CHARON_DATA: &str = "/opt/charon";
+pub(crate) const DKG_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+/// How many lines of charon output to keep in a [`DkgError`]
+const DKG_LOG_LINES: usize = 100;
+/// How long to keep trying to broadcast an exit, while the other operators sign theirs
+const EXIT_BROADCAST_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+const EXIT_BROADCAST_INTERVAL: Duration = Duration::from_secs(30);
+/// Where teardown archives are written, in the blueprint's data dir
+const ARCHIVE_DIR: &str = "archives";
+
+/// Why a DKG ceremony attempt failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DkgFailure {
+    /// The charon container exited with a non-zero code
+    Exited(i64),
+    /// The ceremony didn't complete in time, and the container was stopped
+    TimedOut(Duration),
+    /// The container exited successfully, but no cluster lock was written
+    MissingLock,
+    /// This operator succeeded, but the listed operators failed
+    OtherOperators(Vec<UserID>),
+}
+
+impl fmt::Display for DkgFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DkgFailure::Exited(code) => write!(f, "charon exited with code {code}"),
+            DkgFailure::TimedOut(after) => write!(f, "timed out after {after:?}"),
+            DkgFailure::MissingLock => write!(f, "no cluster lock was produced"),
+            DkgFailure::OtherOperators(user_ids) => {
+                let user_ids = user_ids
+                    .iter()
+                    .map(|user_id| format!("#{user_id}"))
+                    .collect::<Vec<_>>();
+                write!(f, "failed on operators {}", user_ids.join(", "))
+            }
+        }
+    }
+}
+
+/// A failed DKG ceremony, see [`Operator::start_dkg_ceremony`]
+#[derive(Debug)]
+pub struct DkgError {
+    pub failure: DkgFailure,
+    /// The last lines of charon's output
+    pub logs: String,
+    /// Partial output of the ceremony, which has since been removed
+    pub partial_artifacts: Vec<PathBuf>,
+}
+
+impl fmt::Display for DkgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DKG ceremony failed, {}", self.failure)
+    }
+}
+
+impl std::error::Error for DkgError {}
 
 impl Operator {
-    pub async fn new(docker: Arc<Docker>, mut data_dir: PathBuf) -> Result<Operator> {
+    pub async fn new(
+        docker: Arc<Docker>,
+        mut data_dir: PathBuf,
+        env: &CharonEnv,
+        keys: ArchiveKeys,
+    ) -> Result<Operator> {
         let span = tracing::info_span!("operator", path = %data_dir.display());
+        let state_path = data_dir.join(STATE_FILE);
 
-        let repo_path = std::path::absolute(data_dir.join("charon-distributed-validator-node"))?;
+        let node_path = std::path::absolute(data_dir.join("charon-distributed-validator-node"))?;
+        render_node(&node_path).await?;
 
-        if !repo_path.exists() {
-            tracing::warn!("Git repo does not exist, cloning...");
-            let output = Command::new("git")
-                .arg("clone")
-                .arg("https://github.com/ObolNetwork/charon-distributed-validator-node.git")
-                .arg(&repo_path)
-                .output()?;
+        data_dir = node_path;
 
-            if !output.status.success() {
-                return Err(Report::msg(
-                    "Failed to clone charon-distributed-validator-node",
-                ));
-            }
+        let mut state = ClusterState::load(&state_path, &data_dir)?;
+        tracing::info!("Resuming in state {state:?}");
 
-            // TODO: Remove, allow own env
-            std::fs::copy(
-                repo_path.join(".env.sample.holesky"),
-                &repo_path.join(".env"),
-            )?;
+        let mut restored = false;
+        if state == ClusterState::NeedsEnr {
+            if let Some(archive_path) = restore_archive_path() {
+                state = restore_node(&docker, &data_dir, &archive_path, env, &keys).await?;
+                restored = true;
+            }
         }
 
-        data_dir = repo_path;
-
-        let enr_path = data_dir.join(".charon").join("charon-enr-private-key");
         let enr;
-        if enr_path.exists() {
-            tracing::info!("ENR exists, reading from {}", enr_path.display());
-            enr = std::fs::read_to_string(&data_dir.join("enr.pub"))?;
-        } else {
+        if state == ClusterState::NeedsEnr {
             tracing::info!("ENR not found, creating one...");
             enr = create_enr(&docker, &data_dir).await?;
             tracing::info!("Successfully created ENR");
+            state = ClusterState::ExchangingEnrs;
+        } else {
+            tracing::info!(
+                "ENR exists, reading from {}",
+                data_dir.join("enr.pub").display()
+            );
+            enr = std::fs::read_to_string(data_dir.join("enr.pub"))?;
         }
 
-        Ok(Operator {
+        state.save(&state_path).await?;
+
+        let operator = Operator {
             data_dir,
             enr,
+            env: env.clone(),
+            keys,
+            state_path,
+            state: Mutex::new(state),
             docker,
             span,
-        })
+        };
+
+        if restored && !operator.has_slashing_protection() {
+            operator.import_archived_slashing_protection().await?;
+        }
+
+        Ok(operator)
     }
 
     pub fn enr(&self) -> &str {
@@ -75,58 +157,93 @@ impl Operator {
         self.data_dir.as_path()
     }
 
+    pub async fn state(&self) -> ClusterState {
+        *self.state.lock().await
+    }
+
+    /// Move to `state` and persist it, unless the operator is already past it
+    #[tracing::instrument(parent = &self.span, skip(self))]
+    pub async fn advance_state(&self, state: ClusterState) -> Result<()> {
+        let mut current = self.state.lock().await;
+        if *current >= state {
+            return Ok(());
+        }
+
+        state.save(&self.state_path).await?;
+        tracing::info!("Cluster state {:?} -> {state:?}", *current);
+        *current = state;
+
+        Ok(())
+    }
+
+    /// Create the cluster definition for `config`, with this operator's ENR and `config.enrs`
+    ///
+    /// A definition left over from an earlier term or request is reused if it still matches
+    /// `config`, and replaced otherwise.
     #[tracing::instrument(parent = &self.span, skip_all)]
-    pub async fn create_dkg_config(&mut self, config: Option<DkgConfig>) -> Result<()> {
+    pub async fn create_dkg_config(&self, config: &DkgConfig) -> Result<()> {
         let dkg_conf_path = self
             .data_dir
             .join(".charon")
             .join("cluster-definition.json");
         if dkg_conf_path.exists() {
             tracing::info!("DKG config exists at: {}", dkg_conf_path.display());
-        }
-
-        let Some(config) = config else {
-            return Ok(());
-        };
 
-        if dkg_conf_path.exists() {
-            return Ok(());
+            let existing = self
+                .fetch_dkg_config()
+                .await
+                .and_then(|definition| definition.verify(&self.enr, config.enrs.len() + 1, config));
+            match existing {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    tracing::warn!("Replacing the DKG config, it doesn't match the request: {e}");
+                    tokio::fs::remove_file(&dkg_conf_path).await?;
+                }
+            }
+        } else {
+            tracing::info!("DKG configuration not found, creating one...");
         }
 
-        tracing::info!("DKG configuration not found, creating one...");
-
         let other_operator_enrs = config.enrs.join(",");
         let enrs = format!("{},{other_operator_enrs}", self.enr);
+        let fee_recipients = config.fee_recipients.join(",");
+        let withdrawal_addresses = config.withdrawal_addresses.join(",");
+        let validator_count = config.validator_count.to_string();
+        let network_args = config.network.charon_args();
 
         let mut container = Container::new(&self.docker, IMAGE.to_string());
 
+        let mut cmd = vec![
+            "create",
+            "dkg",
+            "--name",
+            config.name.as_str(),
+            "--num-validators",
+            validator_count.as_str(),
+            "--fee-recipient-addresses",
+            fee_recipients.as_str(),
+            "--withdrawal-addresses",
+            withdrawal_addresses.as_str(),
+            "--operator-enrs",
+            enrs.as_str(),
+        ];
+        cmd.extend(network_args.iter().map(String::as_str));
+
         container
-            .cmd(vec![
-                "create",
-                "dkg",
-                "--name",
-                config.name.as_str(),
-                "--num-validators",
-                config.validator_count.to_string().as_str(),
-                "--fee-recipient-addresses",
-                config.todo_bogus_fee_recipient_address.as_str(),
-                "--withdrawal-addresses",
-                config.todo_bogus_withdraw_address.as_str(),
-                "--operator-enrs",
-                enrs.as_str(),
-            ])
+            .cmd(cmd)
             .binds(vec![format!("{}:{CHARON_DATA}", self.data_dir.display())]);
 
         container.start(true).await?;
         container.remove(None).await?;
 
         tracing::info!("Successfully created DKG config");
+        self.advance_state(ClusterState::DkgPending).await?;
 
         Ok(())
     }
 
     #[tracing::instrument(parent = &self.span, skip_all)]
-    pub async fn fetch_dkg_config(&self) -> Result<String> {
+    pub async fn fetch_dkg_config(&self) -> Result<ClusterDefinition> {
         let content = tokio::fs::read_to_string(
             &self
                 .data_dir
@@ -135,26 +252,75 @@ impl Operator {
         )
         .await?;
 
-        Ok(content)
+        ClusterDefinition::from_json(content)
     }
 
     #[tracing::instrument(parent = &self.span, skip_all)]
-    pub async fn copy_in_dkg_config(&self, config: String) -> Result<()> {
+    pub async fn copy_in_dkg_config(&self, config: &ClusterDefinition) -> Result<()> {
         tokio::fs::write(
             self.data_dir
                 .join(".charon")
                 .join("cluster-definition.json"),
-            config,
+            config.as_json(),
         )
         .await?;
 
-        Ok(())
+        self.advance_state(ClusterState::DkgPending).await
+    }
+
+    pub fn has_cluster_lock(&self) -> bool {
+        self.data_dir
+            .join(".charon")
+            .join("cluster-lock.json")
+            .exists()
     }
 
+    #[tracing::instrument(parent = &self.span, skip_all)]
+    pub async fn fetch_cluster_lock(&self) -> Result<ClusterLock> {
+        let content =
+            tokio::fs::read_to_string(self.data_dir.join(".charon").join("cluster-lock.json"))
+                .await?;
+
+        ClusterLock::from_json(&content)
+    }
+
+    /// Read the deposit data generated by the DKG ceremony
+    ///
+    /// Charon writes a `deposit-data.json`, or one `deposit-data-<amount>eth.json` per deposit
+    /// amount when partial deposits are used.
+    #[tracing::instrument(parent = &self.span, skip_all)]
+    pub async fn fetch_deposit_data(&self, network: &Network) -> Result<Vec<DepositData>> {
+        let mut deposits = Vec::new();
+
+        let mut entries = tokio::fs::read_dir(self.data_dir.join(".charon")).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let file_name = entry.file_name();
+            let file_name = file_name.to_string_lossy();
+            if !(file_name.starts_with("deposit-data") && file_name.ends_with(".json")) {
+                continue;
+            }
+
+            tracing::debug!("Reading deposit data from {file_name}");
+            let content = tokio::fs::read_to_string(entry.path()).await?;
+            deposits.extend(DepositData::parse_file(&content, network)?);
+        }
+
+        if deposits.is_empty() {
+            return Err(Report::msg(
+                "No deposit data found, has the DKG ceremony run?",
+            ));
+        }
+
+        Ok(deposits)
+    }
+
+    /// Run the DKG ceremony, producing the cluster lock
+    ///
+    /// If the ceremony fails, any partial output is removed so it can be attempted again, and the
+    /// returned report wraps a [`DkgError`].
     #[tracing::instrument(parent = &self.span, skip_all)]
     pub async fn start_dkg_ceremony(&self) -> Result<()> {
-        let cluster_lock_path = self.data_dir.join(".charon").join("cluster-lock.json");
-        if cluster_lock_path.exists() {
+        if self.has_cluster_lock() {
             tracing::info!("Skipping DKG ceremony, already performed");
             return Ok(());
         }
@@ -166,33 +332,510 @@ impl Operator {
             .cmd(vec!["dkg", "--publish"])
             .binds(vec![format!("{}:{CHARON_DATA}", self.data_dir.display())]);
 
-        container.start(true).await?;
+        container.start(false).await?;
+
+        let timed_out = match tokio::time::timeout(DKG_TIMEOUT, container.wait()).await {
+            Ok(result) => {
+                if let Err(e) = result {
+                    tracing::debug!("DKG container exited with an error: {e}");
+                }
+                false
+            }
+            Err(_) => {
+                tracing::error!("DKG ceremony timed out, stopping container");
+                container.stop().await?;
+                true
+            }
+        };
+
+        let (logs, exit_code) = match container.id() {
+            Some(id) => (
+                container_logs(&self.docker, id, DKG_LOG_LINES).await,
+                self.docker
+                    .inspect_container(id, None)
+                    .await?
+                    .state
+                    .and_then(|state| state.exit_code),
+            ),
+            None => (String::new(), None),
+        };
         container.remove(None).await?;
 
-        if !cluster_lock_path.exists() {
-            todo!("How to handle failure?");
+        let failure = match exit_code {
+            _ if timed_out => DkgFailure::TimedOut(DKG_TIMEOUT),
+            Some(code) if code != 0 => DkgFailure::Exited(code),
+            _ if !self.has_cluster_lock() => DkgFailure::MissingLock,
+            _ => {
+                tracing::info!("DKG ceremony succeeded");
+                // The validators are new, they have no signing history to protect
+                tokio::fs::create_dir_all(self.client_data_dir()).await?;
+                return Ok(());
+            }
+        };
+
+        tracing::error!("DKG ceremony failed: {failure}");
+        let partial_artifacts = self.remove_dkg_artifacts().await?;
+
+        Err(DkgError {
+            failure,
+            logs,
+            partial_artifacts,
         }
+        .into())
+    }
+
+    /// Remove the output of a DKG ceremony, returning the paths that were removed
+    ///
+    /// The ENR private key and the cluster definition are kept, so the ceremony can be run again.
+    #[tracing::instrument(parent = &self.span, skip_all)]
+    pub async fn remove_dkg_artifacts(&self) -> Result<Vec<PathBuf>> {
+        let charon_dir = self.data_dir.join(".charon");
+        let mut removed = Vec::new();
+
+        let mut entries = tokio::fs::read_dir(&charon_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let file_name = entry.file_name();
+            let file_name = file_name.to_string_lossy();
+
+            let path = entry.path();
+            if file_name == "validator_keys" {
+                tokio::fs::remove_dir_all(&path).await?;
+            } else if file_name == "cluster-lock.json"
+                || (file_name.starts_with("deposit-data") && file_name.ends_with(".json"))
+            {
+                tokio::fs::remove_file(&path).await?;
+            } else {
+                continue;
+            }
 
-        tracing::info!("DKG ceremony succeeded");
+            tracing::warn!("Removed DKG artifact {}", path.display());
+            removed.push(path);
+        }
 
-        Ok(())
+        Ok(removed)
     }
 
     #[tracing::instrument(parent = &self.span, skip_all)]
-    pub async fn start_validator(&self) -> Result<String> {
+    pub async fn start_validator(&self) -> Result<ValidatorStack> {
+        if !self.has_slashing_protection() {
+            return Err(eyre!(
+                "Refusing to start the validator without slashing protection data for {}, import the cluster's slashing protection first",
+                self.env.validator_client.name()
+            ));
+        }
+
         tracing::info!("Starting validator");
-        let container_id = docker_compose(&self.data_dir).await?;
-        tracing::debug!("Started with container ID: {container_id}");
+        let stack = ValidatorStack::start(&self.docker, &self.data_dir, &self.env).await?;
+        self.advance_state(ClusterState::ValidatorRunning).await?;
+
+        Ok(stack)
+    }
+
+    /// The validator client's data dir, which holds its slashing protection DB
+    fn client_data_dir(&self) -> PathBuf {
+        self.data_dir
+            .join("data")
+            .join(self.env.validator_client.name())
+    }
+
+    /// Whether the validator client has slashing protection data on this host
+    ///
+    /// That's the case once the client has run here, slashing protection has been imported, or the
+    /// cluster was created here. A fresh host with only the keys doesn't.
+    pub fn has_slashing_protection(&self) -> bool {
+        self.client_data_dir().exists()
+    }
 
-        Ok(container_id)
+    fn slashing_protection_args(&self, interchange: Interchange) -> Result<Vec<String>> {
+        self.env
+            .validator_client
+            .slashing_protection_args(&self.env.network, interchange)
+            .ok_or_else(|| {
+                eyre!(
+                    "{} can't export or import slashing protection",
+                    self.env.validator_client.name()
+                )
+            })
+    }
+
+    /// Export the validator client's slashing protection DB as EIP-3076 interchange JSON
+    ///
+    /// The validator is stopped first and isn't restarted, so nothing is signed after the export.
+    #[tracing::instrument(parent = &self.span, skip_all)]
+    pub async fn export_slashing_protection(&self) -> Result<Vec<u8>> {
+        let args = self.slashing_protection_args(Interchange::Export)?;
+        if !self.has_slashing_protection() {
+            return Err(eyre!("There is no slashing protection DB to export"));
+        }
+
+        ValidatorStack::stop(&self.docker, &ValidatorStack::name(&self.data_dir)).await?;
+
+        let interchange_path = self.client_data_dir().join(INTERCHANGE_FILE);
+        if interchange_path.exists() {
+            tokio::fs::remove_file(&interchange_path).await?;
+        }
+
+        ValidatorStack::run_validator_client(&self.docker, &self.data_dir, &self.env, args)
+            .await
+            .map_err(|e| e.wrap_err("Failed to export slashing protection"))?;
+
+        let interchange = tokio::fs::read(&interchange_path).await?;
+        let protection = SlashingProtection::parse(&interchange)?;
+        tracing::info!(
+            "Exported slashing protection for {} validators",
+            protection.data.len()
+        );
+
+        Ok(interchange)
+    }
+
+    /// Import EIP-3076 interchange JSON into the validator client's slashing protection DB
+    ///
+    /// The validator is stopped first, if it's running. Clients that can't import interchange files
+    /// only accept one without any signing history, as there's nothing to protect.
+    #[tracing::instrument(parent = &self.span, skip_all)]
+    pub async fn import_slashing_protection(
+        &self,
+        interchange: &[u8],
+    ) -> Result<SlashingProtection> {
+        let protection = SlashingProtection::parse(interchange)?;
+        let args = match self.slashing_protection_args(Interchange::Import) {
+            Ok(args) => args,
+            Err(_) if !protection.has_history() => {
+                tokio::fs::create_dir_all(self.client_data_dir()).await?;
+                tracing::info!("Slashing protection has no signing history, nothing to import");
+                return Ok(protection);
+            }
+            Err(e) => return Err(e),
+        };
+
+        ValidatorStack::stop(&self.docker, &ValidatorStack::name(&self.data_dir)).await?;
+
+        let client_data_dir = self.client_data_dir();
+        let fresh = !client_data_dir.exists();
+        tokio::fs::create_dir_all(&client_data_dir).await?;
+        tokio::fs::write(client_data_dir.join(INTERCHANGE_FILE), interchange).await?;
+
+        let result =
+            ValidatorStack::run_validator_client(&self.docker, &self.data_dir, &self.env, args)
+                .await;
+        if let Err(e) = result {
+            // Don't leave the data dir behind, it would count as having slashing protection
+            if fresh {
+                tokio::fs::remove_dir_all(&client_data_dir).await?;
+            }
+            return Err(e.wrap_err("Failed to import slashing protection"));
+        }
+
+        tracing::info!(
+            "Imported slashing protection for {} validators",
+            protection.data.len()
+        );
+        Ok(protection)
+    }
+
+    /// Import the interchange exported on teardown by another validator client, for a node restored
+    /// on a host running a different client, see [`Operator::teardown`]
+    ///
+    /// The validator is only started if the import succeeds, otherwise the interchange must be
+    /// imported with [`import_slashing_protection`](crate::import_slashing_protection).
+    async fn import_archived_slashing_protection(&self) -> Result<()> {
+        let mut interchange_path = None;
+        let data_dir = self.data_dir.join("data");
+        if data_dir.exists() {
+            let mut entries = tokio::fs::read_dir(&data_dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path().join(INTERCHANGE_FILE);
+                if path.exists() {
+                    interchange_path = Some(path);
+                    break;
+                }
+            }
+        }
+
+        let Some(interchange_path) = interchange_path else {
+            tracing::warn!(
+                "Restored the node, but not starting the validator without slashing protection data for {}",
+                self.env.validator_client.name()
+            );
+            return Ok(());
+        };
+
+        tracing::info!(
+            "Importing the slashing protection exported to {}",
+            interchange_path.display()
+        );
+        let interchange = tokio::fs::read(&interchange_path).await?;
+        match self.import_slashing_protection(&interchange).await {
+            Ok(_) => self.advance_state(ClusterState::ValidatorRunning).await,
+            Err(e) => {
+                tracing::warn!(
+                    "Restored the node, but not starting the validator, importing slashing protection failed: {e:?}"
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Sign this operator's partial exit for `pubkey`, or [`ALL_VALIDATORS`], and publish it to
+    /// the Obol API
+    #[tracing::instrument(parent = &self.span, skip(self))]
+    pub async fn sign_exit(&self, pubkey: &str) -> Result<()> {
+        tracing::info!("Signing partial exit");
+        ValidatorStack::run_charon(&self.docker, &self.data_dir, self.exit_args("sign", pubkey))
+            .await
+            .map_err(|e| e.wrap_err("Failed to sign the exit"))?;
+
+        Ok(())
+    }
+
+    /// Broadcast the exit for `pubkey`, or [`ALL_VALIDATORS`], to the beacon node
+    ///
+    /// The full exit is only available once a threshold of operators have signed theirs, so this
+    /// is retried until [`EXIT_BROADCAST_TIMEOUT`].
+    #[tracing::instrument(parent = &self.span, skip(self))]
+    pub async fn broadcast_exit(&self, pubkey: &str) -> Result<()> {
+        let deadline = tokio::time::Instant::now() + EXIT_BROADCAST_TIMEOUT;
+        loop {
+            tracing::info!("Broadcasting exit");
+            let args = self.exit_args("broadcast", pubkey);
+            let Err(e) = ValidatorStack::run_charon(&self.docker, &self.data_dir, args).await
+            else {
+                tracing::info!("Exit broadcast");
+                return Ok(());
+            };
+
+            if tokio::time::Instant::now() + EXIT_BROADCAST_INTERVAL > deadline {
+                return Err(e.wrap_err(format!(
+                    "Failed to broadcast the exit within {EXIT_BROADCAST_TIMEOUT:?}"
+                )));
+            }
+
+            tracing::warn!("Exit not broadcast yet, retrying in {EXIT_BROADCAST_INTERVAL:?}: {e}");
+            tokio::time::sleep(EXIT_BROADCAST_INTERVAL).await;
+        }
+    }
+
+    /// Stop the validator, archive the node's key material and remove the node
+    ///
+    /// `.charon`, with the cluster lock and validator keys, and the validator client's data dir,
+    /// with its slashing protection DB and an [`INTERCHANGE_FILE`] exported from it, are written to
+    /// an archive encrypted with the passphrase. The node is only removed once the archive has been
+    /// written and read back, with the cluster lock if the node has one.
+    #[tracing::instrument(parent = &self.span, skip_all)]
+    pub async fn teardown(&self) -> Result<PathBuf> {
+        let key = self.keys.passphrase()?;
+        let name = ValidatorStack::name(&self.data_dir);
+        ValidatorStack::stop(&self.docker, &name).await?;
+
+        if self.has_slashing_protection() {
+            if self.slashing_protection_args(Interchange::Export).is_ok() {
+                self.export_slashing_protection().await?;
+            } else {
+                tracing::warn!(
+                    "{} can't export slashing protection, only its DB is archived",
+                    self.env.validator_client.name()
+                );
+            }
+        }
+
+        let client_data = format!("data/{}", self.env.validator_client.name());
+        let archive = create_archive(&self.data_dir, &[".charon", &client_data], &key)?;
+
+        // A node torn down before its DKG ceremony has no lock to archive
+        let archived = list_archive(&archive, &self.keys)?;
+        if self.has_cluster_lock()
+            && !archived
+                .iter()
+                .any(|path| path.ends_with(".charon/cluster-lock.json"))
+        {
+            return Err(eyre!(
+                "Refusing to tear down, the archive is missing the cluster lock"
+            ));
+        }
+
+        let archive_dir = self.state_path.with_file_name(ARCHIVE_DIR);
+        tokio::fs::create_dir_all(&archive_dir).await?;
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let archive_path = archive_dir.join(format!("{name}-{timestamp}.tar.enc"));
+        let tmp_path = archive_path.with_extension("enc.tmp");
+        tokio::fs::write(&tmp_path, &archive).await?;
+        tokio::fs::set_permissions(&tmp_path, std::fs::Permissions::from_mode(0o600)).await?;
+        tokio::fs::rename(&tmp_path, &archive_path).await?;
+        tracing::info!(
+            "Archived {} files to {}",
+            archived.len(),
+            archive_path.display()
+        );
+
+        tokio::fs::remove_dir_all(&self.data_dir).await?;
+        tokio::fs::remove_file(&self.state_path).await?;
+        tracing::info!("Removed {}", self.data_dir.display());
+
+        Ok(archive_path)
+    }
+
+    /// Back up the ENR private key and validator keys to an encrypted, checksummed archive, see
+    /// [`BACKUP_PATHS`]
+    #[tracing::instrument(parent = &self.span, skip_all)]
+    pub async fn backup(&self, config: &BackupConfig) -> Result<PathBuf> {
+        if !self
+            .data_dir
+            .join(".charon")
+            .join("charon-enr-private-key")
+            .exists()
+        {
+            return Err(eyre!("There is no ENR private key to back up"));
+        }
+
+        let archive = create_archive(&self.data_dir, BACKUP_PATHS, &self.keys.backup())?;
+        config.write(&archive).await
+    }
+
+    pub fn archive_keys(&self) -> &ArchiveKeys {
+        &self.keys
+    }
+
+    fn exit_args(&self, command: &str, pubkey: &str) -> Vec<String> {
+        let mut args = vec![
+            String::from("exit"),
+            command.to_string(),
+            format!(
+                "--beacon-node-endpoints={}",
+                self.env.beacon_node_endpoints.join(",")
+            ),
+        ];
+
+        if pubkey == ALL_VALIDATORS {
+            args.push(String::from("--all"));
+        } else {
+            args.push(format!("--validator-public-key={pubkey}"));
+        }
+
+        args
     }
 }
 
+/// Restore a node on a fresh host from an archive of its previous host, see [`Operator::teardown`]
+/// and [`Operator::backup`]
+///
+/// The archive must hold the cluster lock, and the ENR of its private key must be one of the
+/// cluster's operators, so the node rejoins the cluster without a new DKG. Returns the state to
+/// resume in: running the validator, unless the archive has no slashing protection DB for the
+/// validator client.
+async fn restore_node(
+    docker: &Docker,
+    node_dir: &Path,
+    archive_path: &Path,
+    env: &CharonEnv,
+    keys: &ArchiveKeys,
+) -> Result<ClusterState> {
+    tracing::info!("Restoring the node from {}", archive_path.display());
+    let archive = tokio::fs::read(archive_path)
+        .await
+        .map_err(|e| eyre!("Failed to read {}: {e}", archive_path.display()))?;
+    if verify_checksum(archive_path, &archive).await? {
+        tracing::info!("Archive checksum matches");
+    }
+
+    // Unpack next to the node, so nothing is moved into it before the archive is verified
+    let staging_dir = node_dir.with_extension("restore");
+    if staging_dir.exists() {
+        tokio::fs::remove_dir_all(&staging_dir).await?;
+    }
+
+    let verified = async {
+        extract_archive(&archive, keys, &staging_dir)?;
+        verify_restored_node(docker, &staging_dir).await
+    }
+    .await;
+    let enr = match verified {
+        Ok(enr) => enr,
+        Err(e) => {
+            if staging_dir.exists() {
+                tokio::fs::remove_dir_all(&staging_dir).await?;
+            }
+            return Err(e.wrap_err(format!(
+                "Failed to restore the node from {}",
+                archive_path.display()
+            )));
+        }
+    };
+
+    let mut moves = vec![(staging_dir.join(".charon"), node_dir.join(".charon"))];
+    let staged_data = staging_dir.join("data");
+    if staged_data.exists() {
+        let mut entries = tokio::fs::read_dir(&staged_data).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            moves.push((entry.path(), node_dir.join("data").join(entry.file_name())));
+        }
+    }
+
+    for (from, to) in moves {
+        // An empty `.charon` is left by rendering the node, anything else isn't ours to replace
+        if to.exists() && tokio::fs::remove_dir(&to).await.is_err() {
+            return Err(eyre!(
+                "Refusing to restore over {}, it isn't empty",
+                to.display()
+            ));
+        }
+        if let Some(parent) = to.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::rename(&from, &to).await?;
+    }
+    tokio::fs::remove_dir_all(&staging_dir).await?;
+    std::fs::write(node_dir.join("enr.pub"), enr.as_bytes())?;
+
+    let client_data_dir = node_dir.join("data").join(env.validator_client.name());
+    if client_data_dir.exists() {
+        tracing::info!("Restored the node, rejoining the cluster");
+        Ok(ClusterState::ValidatorRunning)
+    } else {
+        tracing::info!("Restored the node, the validator client has no slashing protection data");
+        Ok(ClusterState::DkgDone)
+    }
+}
+
+/// Check a node unpacked in `dir` belongs to its cluster, returning its ENR
+async fn verify_restored_node(docker: &Docker, dir: &Path) -> Result<String> {
+    let charon_dir = dir.join(".charon");
+    let lock_path = charon_dir.join("cluster-lock.json");
+    if !lock_path.exists() {
+        return Err(eyre!("The archive has no cluster lock"));
+    }
+    if !charon_dir.join("validator_keys").exists() {
+        return Err(eyre!("The archive has no validator keys"));
+    }
+
+    let lock = ClusterLock::from_json(&tokio::fs::read_to_string(&lock_path).await?)?;
+    let enr = charon_enr(docker, dir, vec!["enr"]).await?;
+    if !lock.contains_enr(&enr) {
+        return Err(eyre!(
+            "The archived ENR {enr} isn't an operator of the cluster {}",
+            lock.lock_hash
+        ));
+    }
+
+    Ok(enr)
+}
+
 async fn create_enr(docker: &Docker, data_dir: &Path) -> Result<String> {
+    let enr = charon_enr(docker, data_dir, vec!["create", "enr"]).await?;
+    std::fs::write(data_dir.join("enr.pub"), enr.as_bytes())?;
+
+    Ok(enr)
+}
+
+/// Run a charon command that prints an ENR, `create enr` or `enr`, and return the ENR
+async fn charon_enr(docker: &Docker, data_dir: &Path, cmd: Vec<&str>) -> Result<String> {
     let mut container = Container::new(docker, IMAGE.to_string());
 
     container
-        .cmd(vec!["create", "enr"])
+        .cmd(cmd)
         .binds(vec![format!("{}:{CHARON_DATA}", data_dir.display())]);
 
     container.create().await?;
@@ -207,8 +850,8 @@ async fn create_enr(docker: &Docker, data_dir: &Path) -> Result<String> {
         }))
         .await
     else {
-        tracing::error!("Failed to create ENR, no output available");
-        return Err(Report::msg("Failed to create ENR").into());
+        tracing::error!("Failed to get the ENR, no output available");
+        return Err(Report::msg("Failed to get the ENR"));
     };
 
     let mut enr = None;
@@ -220,38 +863,18 @@ async fn create_enr(docker: &Docker, data_dir: &Path) -> Result<String> {
 
         for line in out.as_ref().split(|b| *b == b'\n') {
             if line.starts_with(b"enr:-") {
-                enr = Some(String::from_utf8_lossy(line.as_ref()).into_owned());
+                enr = Some(String::from_utf8_lossy(line).into_owned());
                 break;
             }
         }
     }
 
     let Some(enr) = enr else {
-        tracing::error!("Failed to create ENR");
-        return Err(Report::msg("Failed to create ENR").into());
+        tracing::error!("Failed to get the ENR");
+        return Err(Report::msg("Failed to get the ENR"));
     };
 
-    std::fs::write(data_dir.join("enr.pub"), enr.as_bytes())?;
-
     container.remove(None).await?;
 
     Ok(enr)
 }
-
-/// Run `docker compose up -d` and return the container ID
-pub(crate) async fn docker_compose(dir: &Path) -> Result<String> {
-    let _ = Command::new("docker-compose")
-        .arg("up")
-        .arg("-d")
-        .current_dir(dir)
-        .output()?;
-
-    let out = Command::new("docker-compose")
-        .arg("ps")
-        .arg("-q")
-        .arg("charon")
-        .current_dir(dir)
-        .output()?;
-
-    Ok(String::from_utf8_lossy(&out.stdout).to_string())
-}
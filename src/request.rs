@@ -0,0 +1,62 @@
+use super::{DkgConfig, ObolContext};
+use alloy_primitives::Address;
+use alloy_provider::Provider;
+use alloy_sol_types::{sol, SolCall};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use gadget_sdk as sdk;
+use sdk::alloy_rpc_types::TransactionRequest;
+use sdk::clients::tangle::runtime::TangleClient;
+use sdk::ctx::ServicesContext;
+use sdk::tangle_subxt::tangle_testnet_runtime::api::runtime_types::tangle_primitives::services::BlueprintManager;
+
+sol! {
+    function getDkgParams(uint64 serviceId)
+        external
+        view
+        returns (
+            string memory name,
+            uint32 validatorCount,
+            string[] memory feeRecipients,
+            string[] memory withdrawalAddresses
+        );
+}
+
+/// Fetch the DKG parameters the service was requested with
+///
+/// Tangle doesn't keep the service request arguments around once the service is initiated, so
+/// `ObolDvtBlueprint.onRequest` stores them, and they're read back from the contract here.
+pub async fn fetch_requested_params(ctx: &ObolContext, client: &TangleClient) -> Result<DkgConfig> {
+    let service_id = ctx
+        .env
+        .service_id()
+        .ok_or_else(|| eyre!("No service ID configured"))?;
+
+    let blueprint = ctx.current_blueprint(client).await?;
+    let BlueprintManager::Evm(manager) = blueprint.manager;
+
+    let call = getDkgParamsCall {
+        serviceId: service_id,
+    };
+    let tx = TransactionRequest::default()
+        .to(Address::from(manager.0))
+        .input(call.abi_encode().into());
+
+    let provider = sdk::utils::evm::get_provider_http(&ctx.env.http_rpc_endpoint);
+    let output = provider.call(&tx).await?;
+    let params = getDkgParamsCall::abi_decode_returns(&output, true)?;
+
+    tracing::info!(
+        name = %params.name,
+        validators = params.validatorCount,
+        "Fetched DKG parameters for service {service_id}"
+    );
+
+    DkgConfig::new(
+        params.name,
+        ctx.charon_env.network.clone(),
+        params.validatorCount,
+        params.feeRecipients,
+        params.withdrawalAddresses,
+    )
+}
@@ -0,0 +1,129 @@
+use super::ObolContext;
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use gadget_sdk as sdk;
+use sdk::clients::tangle::runtime::TangleClient;
+use sdk::ctx::ServicesContext;
+use sdk::ext::sp_core::{ecdsa, Pair};
+use sdk::ext::subxt::tx::Signer;
+use sdk::ext::subxt::utils::AccountId32;
+use sdk::keystore::BackendExt;
+use sdk::network::channels::UserID;
+use sdk::tangle_subxt::tangle_testnet_runtime::api;
+
+/// An operator of this service, as registered on Tangle
+#[derive(Debug, Clone)]
+pub struct ServiceOperator {
+    pub account: AccountId32,
+    /// The key the operator registered for the blueprint, which signs its protocol messages
+    pub ecdsa_key: ecdsa::Public,
+}
+
+/// The operators of this service
+///
+/// An operator's position in the service, as listed by Tangle, is its [`UserID`] in protocol
+/// messages.
+#[derive(Debug, Clone)]
+pub struct ServiceOperators {
+    operators: Vec<ServiceOperator>,
+    my_user_id: UserID,
+}
+
+impl ServiceOperators {
+    pub async fn fetch(ctx: &ObolContext, client: &TangleClient) -> Result<Self> {
+        let blueprint_id = ctx
+            .env
+            .protocol_specific
+            .tangle()
+            .map_err(|e| eyre!(e))?
+            .blueprint_id;
+        let my_account = ctx.env.first_sr25519_signer()?.account_id();
+        let my_ecdsa_key = ctx.env.keystore()?.ecdsa_key()?.signer().public();
+
+        let storage = client.storage().at_latest().await?;
+
+        let mut operators = Vec::new();
+        for (account, _) in ctx.current_service_operators(client).await? {
+            let preferences = storage
+                .fetch(&api::storage().services().operators(blueprint_id, &account))
+                .await?
+                .ok_or_else(|| {
+                    eyre!("Operator {account} is not registered for blueprint {blueprint_id}")
+                })?;
+
+            operators.push(ServiceOperator {
+                account,
+                ecdsa_key: ecdsa::Public::from_raw(preferences.key),
+            });
+        }
+
+        let position = operators
+            .iter()
+            .position(|op| op.account == my_account)
+            .ok_or_else(|| eyre!("Operator is not part of the service"))?;
+
+        if operators[position].ecdsa_key != my_ecdsa_key {
+            return Err(eyre!(
+                "Keystore ECDSA key {my_ecdsa_key} doesn't match the key registered on Tangle"
+            ));
+        }
+
+        Ok(Self {
+            operators,
+            my_user_id: position as UserID,
+        })
+    }
+
+    /// Operators that were not fetched from Tangle
+    #[cfg(test)]
+    pub(crate) fn new(operators: Vec<ServiceOperator>, my_user_id: UserID) -> Self {
+        Self {
+            operators,
+            my_user_id,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.operators.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operators.is_empty()
+    }
+
+    pub fn my_user_id(&self) -> UserID {
+        self.my_user_id
+    }
+
+    /// The leader of an exchange term, operators take turns in order
+    pub fn leader(&self, term: u32) -> UserID {
+        (term as usize % self.operators.len()) as UserID
+    }
+
+    pub fn get(&self, user_id: UserID) -> Option<&ServiceOperator> {
+        self.operators.get(user_id as usize)
+    }
+
+    /// The [`UserID`]s of all operators
+    pub fn user_ids(&self) -> impl Iterator<Item = UserID> {
+        0..self.operators.len() as UserID
+    }
+
+    /// The [`UserID`]s of all operators other than this one
+    pub fn peers(&self) -> impl Iterator<Item = UserID> + '_ {
+        self.user_ids()
+            .filter(|user_id| *user_id != self.my_user_id)
+    }
+
+    /// A readable list of operators, e.g. `#1 (5Grw...), #2 (5FHn...)`
+    pub fn describe(&self, user_ids: impl IntoIterator<Item = UserID>) -> String {
+        user_ids
+            .into_iter()
+            .map(|user_id| match self.get(user_id) {
+                Some(operator) => format!("#{user_id} ({})", operator.account),
+                None => format!("#{user_id}"),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
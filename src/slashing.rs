@@ -0,0 +1,67 @@
+use crate::check_hex;
+use color_eyre::eyre::{bail, eyre};
+use color_eyre::Result;
+use serde::Deserialize;
+
+/// The only interchange format version in use, see EIP-3076
+const INTERCHANGE_FORMAT_VERSION: &str = "5";
+
+/// An EIP-3076 slashing protection interchange, the blocks and attestations validators have signed
+#[derive(Debug, Clone, Deserialize)]
+pub struct SlashingProtection {
+    pub metadata: InterchangeMetadata,
+    pub data: Vec<ProtectedValidator>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InterchangeMetadata {
+    pub interchange_format_version: String,
+    pub genesis_validators_root: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProtectedValidator {
+    pub pubkey: String,
+    #[serde(default)]
+    pub signed_blocks: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub signed_attestations: Vec<serde_json::Value>,
+}
+
+impl SlashingProtection {
+    pub fn parse(content: &[u8]) -> Result<Self> {
+        let protection: Self = serde_json::from_slice(content)
+            .map_err(|e| eyre!("Invalid slashing protection interchange: {e}"))?;
+        protection.validate()?;
+
+        Ok(protection)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.metadata.interchange_format_version != INTERCHANGE_FORMAT_VERSION {
+            bail!(
+                "Unsupported slashing protection interchange format version `{}`, expected `{INTERCHANGE_FORMAT_VERSION}`",
+                self.metadata.interchange_format_version
+            );
+        }
+
+        check_hex(
+            "genesis validators root",
+            &self.metadata.genesis_validators_root,
+            32,
+        )?;
+
+        for validator in &self.data {
+            check_hex("validator public key", &validator.pubkey, 48)?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether any validator has signed a block or an attestation
+    pub fn has_history(&self) -> bool {
+        self.data.iter().any(|validator| {
+            !validator.signed_blocks.is_empty() || !validator.signed_attestations.is_empty()
+        })
+    }
+}
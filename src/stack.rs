@@ -0,0 +1,622 @@
+use crate::{CharonEnv, Network, BEACON_NODE_ADDRESS, CHARON_DATA, DATA_DIR, IMAGE, KEYS_DIR};
+use bollard::container::{
+    Config, CreateContainerOptions, LogsOptions, NetworkingConfig, RemoveContainerOptions,
+};
+use bollard::errors::Error as DockerError;
+use bollard::image::CreateImageOptions;
+use bollard::models::{
+    EndpointSettings, HealthConfig, HostConfig, PortBinding, RestartPolicy, RestartPolicyNameEnum,
+};
+use bollard::network::{CreateNetworkOptions, InspectNetworkOptions};
+use bollard::Docker;
+use color_eyre::eyre::{bail, eyre};
+use color_eyre::Result;
+use gadget_sdk as sdk;
+use sdk::docker::bollard;
+use sdk::ext::sp_core::hashing::blake2_256;
+use sdk::futures::{StreamExt, TryStreamExt};
+use std::collections::HashMap;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::time::Duration;
+
+const MEV_BOOST_IMAGE: &str = "flashbots/mev-boost:1.8.1";
+const PROMETHEUS_IMAGE: &str = "prom/prometheus:v2.50.1";
+/// The user the Prometheus image runs as, `nobody`
+const PROMETHEUS_UID: u32 = 65534;
+
+/// Every service the stack may run, so containers of services that are no longer wanted are removed
+const SERVICES: &[&str] = &[
+    "charon",
+    VALIDATOR_CLIENT,
+    VALIDATOR_CLIENT_IMPORT,
+    VALIDATOR_CLIENT_ONE_OFF,
+    CHARON_ONE_OFF,
+    "mev-boost",
+    "prometheus",
+];
+
+/// The service running the operator's [`ValidatorClient`](crate::ValidatorClient)
+const VALIDATOR_CLIENT: &str = "validator-client";
+/// One-off containers importing the keys, for clients that need it
+const VALIDATOR_CLIENT_IMPORT: &str = "validator-client-import";
+/// One-off containers of the validator client, see [`ValidatorStack::run_validator_client`]
+const VALIDATOR_CLIENT_ONE_OFF: &str = "validator-client-one-off";
+/// One-off charon commands, see [`ValidatorStack::run_charon`]
+const CHARON_ONE_OFF: &str = "charon-one-off";
+
+/// Label marking the containers of a stack, with the stack's name as its value
+const STACK_LABEL: &str = "tangle.obol.stack";
+
+/// How long the containers must keep running after being started
+const STARTUP_GRACE: Duration = Duration::from_secs(5);
+
+/// How many lines of output to include when a container fails to start
+const FAILURE_LOG_LINES: usize = 50;
+
+/// A running container of the validator stack
+#[derive(Debug, Clone)]
+pub struct StackContainer {
+    /// The service it runs, e.g. `charon`
+    pub service: &'static str,
+    pub name: String,
+    pub id: String,
+}
+
+/// The containers of a node: charon, the operator's validator client, Prometheus and optionally
+/// MEV-boost
+#[derive(Debug, Clone)]
+pub struct ValidatorStack {
+    pub name: String,
+    pub containers: Vec<StackContainer>,
+}
+
+/// How to run a service of the stack
+struct ServiceSpec {
+    service: &'static str,
+    image: String,
+    entrypoint: Option<Vec<String>>,
+    cmd: Option<Vec<String>>,
+    env: Vec<String>,
+    binds: Vec<String>,
+    /// Published on the host, over TCP
+    ports: Vec<u16>,
+    healthcheck: Option<HealthConfig>,
+    /// Whether the container is restarted when it stops, otherwise it is expected to run once
+    restart: bool,
+}
+
+impl ValidatorStack {
+    /// The name of the stack running from `node_dir`, unique per node on a host
+    pub fn name(node_dir: &Path) -> String {
+        let hash = blake2_256(node_dir.to_string_lossy().as_bytes());
+        format!("obol-{}", hex::encode(&hash[..4]))
+    }
+
+    /// Start the stack, replacing any containers left from an earlier start
+    ///
+    /// Fails if an image can't be pulled, a container can't be created, or a container stops
+    /// within [`STARTUP_GRACE`] of being started.
+    #[tracing::instrument(skip_all, fields(stack))]
+    pub async fn start(docker: &Docker, node_dir: &Path, env: &CharonEnv) -> Result<Self> {
+        check_network(env)?;
+        let name = Self::name(node_dir);
+        tracing::Span::current().record("stack", &name);
+
+        Self::remove_containers(docker, &name).await?;
+        ensure_network(docker, &name).await?;
+        import_validator_keys(docker, &name, node_dir, env).await?;
+        create_prometheus_data_dir(node_dir)?;
+
+        let mut containers = Vec::new();
+        for spec in service_specs(node_dir, env) {
+            pull_image(docker, &spec.image).await?;
+            containers.push(create_container(docker, &name, spec).await?);
+        }
+
+        for container in &containers {
+            docker
+                .start_container::<String>(&container.id, None)
+                .await
+                .map_err(|e| eyre!("Failed to start {}: {e}", container.service))?;
+            tracing::info!("Started {} ({})", container.service, container.name);
+        }
+
+        tokio::time::sleep(STARTUP_GRACE).await;
+        for container in &containers {
+            let state = docker
+                .inspect_container(&container.id, None)
+                .await?
+                .state
+                .unwrap_or_default();
+
+            if state.running != Some(true) || state.restarting == Some(true) {
+                let logs = container_logs(docker, &container.id, FAILURE_LOG_LINES).await;
+                bail!(
+                    "{} stopped after starting (exit code {:?}):\n{logs}",
+                    container.service,
+                    state.exit_code
+                );
+            }
+        }
+
+        Ok(Self { name, containers })
+    }
+
+    /// Stop and remove every container of the stack named `name`
+    pub async fn remove_containers(docker: &Docker, name: &str) -> Result<()> {
+        for service in SERVICES {
+            let container_name = format!("{name}-{service}");
+            let options = RemoveContainerOptions {
+                force: true,
+                ..Default::default()
+            };
+
+            match docker
+                .remove_container(&container_name, Some(options))
+                .await
+            {
+                Ok(()) => tracing::info!("Removed container {container_name}"),
+                Err(DockerError::DockerResponseServerError {
+                    status_code: 404, ..
+                }) => {}
+                Err(e) => return Err(eyre!("Failed to remove {container_name}: {e}")),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Run the operator's validator client once, with `args` to its image's entrypoint and the same
+    /// mounts as in the stack
+    ///
+    /// The stack must be stopped, the client's data dir isn't shared with the running client.
+    pub async fn run_validator_client(
+        docker: &Docker,
+        node_dir: &Path,
+        env: &CharonEnv,
+        args: Vec<String>,
+    ) -> Result<()> {
+        check_network(env)?;
+        let name = Self::name(node_dir);
+        ensure_network(docker, &name).await?;
+
+        let spec = ServiceSpec {
+            service: VALIDATOR_CLIENT_ONE_OFF,
+            entrypoint: None,
+            restart: false,
+            ..validator_client_spec(&node_dir.display().to_string(), env, args)
+        };
+        run_one_off(docker, &name, spec).await
+    }
+
+    /// Run a charon command once, with the node's `.charon` in its data dir
+    pub async fn run_charon(docker: &Docker, node_dir: &Path, args: Vec<String>) -> Result<()> {
+        let name = Self::name(node_dir);
+        ensure_network(docker, &name).await?;
+
+        let spec = ServiceSpec {
+            service: CHARON_ONE_OFF,
+            image: IMAGE.to_string(),
+            entrypoint: None,
+            cmd: Some(args),
+            env: Vec::new(),
+            binds: vec![format!("{}:{CHARON_DATA}", node_dir.display())],
+            ports: Vec::new(),
+            healthcheck: None,
+            restart: false,
+        };
+        run_one_off(docker, &name, spec).await
+    }
+
+    /// Remove the stack's containers and its network
+    pub async fn stop(docker: &Docker, name: &str) -> Result<()> {
+        Self::remove_containers(docker, name).await?;
+
+        match docker.remove_network(name).await {
+            Ok(()) => tracing::info!("Removed network {name}"),
+            Err(DockerError::DockerResponseServerError {
+                status_code: 404, ..
+            }) => {}
+            Err(e) => return Err(eyre!("Failed to remove network {name}: {e}")),
+        }
+
+        Ok(())
+    }
+}
+
+/// The validator clients are started with `NETWORK` set to the network's name, which they only
+/// know for public networks
+fn check_network(env: &CharonEnv) -> Result<()> {
+    if let Network::Devnet { name, .. } = &env.network {
+        bail!(
+            "The validator clients don't support devnet `{name}`, the validator can't be started"
+        );
+    }
+
+    Ok(())
+}
+
+fn service_specs(node_dir: &Path, env: &CharonEnv) -> Vec<ServiceSpec> {
+    let node_dir = node_dir.display().to_string();
+    let seconds = |secs: i64| secs * 1_000_000_000;
+
+    let mut charon_env = vec![
+        format!(
+            "CHARON_BEACON_NODE_ENDPOINTS={}",
+            env.beacon_node_endpoints.join(",")
+        ),
+        format!("CHARON_LOG_LEVEL={}", env.log_level),
+        String::from("CHARON_LOG_FORMAT=console"),
+        String::from("CHARON_P2P_RELAYS=https://0.relay.obol.tech,https://1.relay.obol.dev"),
+        format!("CHARON_P2P_TCP_ADDRESS=0.0.0.0:{}", env.p2p_tcp_port),
+        String::from("CHARON_VALIDATOR_API_ADDRESS=0.0.0.0:3600"),
+        String::from("CHARON_MONITORING_ADDRESS=0.0.0.0:3620"),
+        format!("CHARON_BUILDER_API={}", env.builder_api()),
+    ];
+    if let Some(hostname) = &env.p2p_external_hostname {
+        charon_env.push(format!("CHARON_P2P_EXTERNAL_HOSTNAME={hostname}"));
+    }
+    if let Some(endpoint) = &env.execution_endpoint {
+        charon_env.push(format!("CHARON_EXECUTION_CLIENT_RPC_ENDPOINT={endpoint}"));
+    }
+
+    let mut specs = vec![
+        ServiceSpec {
+            service: "charon",
+            image: IMAGE.to_string(),
+            entrypoint: None,
+            cmd: Some(vec![String::from("run")]),
+            env: charon_env,
+            binds: vec![format!("{node_dir}/.charon:/opt/charon/.charon")],
+            ports: vec![env.p2p_tcp_port],
+            restart: true,
+            healthcheck: Some(HealthConfig {
+                test: Some(vec![
+                    String::from("CMD-SHELL"),
+                    String::from("wget -qO- http://localhost:3620/readyz"),
+                ]),
+                interval: Some(seconds(30)),
+                timeout: Some(seconds(10)),
+                retries: Some(3),
+                start_period: Some(seconds(60)),
+                ..Default::default()
+            }),
+        },
+        validator_client_spec(
+            &node_dir,
+            env,
+            env.validator_client.args(&env.network, env.builder_api()),
+        ),
+        ServiceSpec {
+            service: "prometheus",
+            image: PROMETHEUS_IMAGE.to_string(),
+            entrypoint: Some(vec![String::from("/etc/prometheus/run.sh")]),
+            cmd: None,
+            env: vec![format!(
+                "PROM_REMOTE_WRITE_TOKEN={}",
+                env.monitoring_token.clone().unwrap_or_default()
+            )],
+            binds: vec![
+                format!("{node_dir}/prometheus:/etc/prometheus"),
+                format!("{node_dir}/data/prometheus:/prometheus"),
+            ],
+            ports: Vec::new(),
+            healthcheck: None,
+            restart: true,
+        },
+    ];
+
+    if env.builder_api() {
+        specs.push(ServiceSpec {
+            service: "mev-boost",
+            image: MEV_BOOST_IMAGE.to_string(),
+            entrypoint: None,
+            cmd: Some(vec![
+                env.network.mev_boost_args(),
+                String::from("-loglevel=info"),
+                String::from("-addr=0.0.0.0:18550"),
+                String::from("-relay-check"),
+                format!("-relays={}", env.mev_boost_relays.join(",")),
+            ]),
+            env: Vec::new(),
+            binds: Vec::new(),
+            ports: vec![18550],
+            healthcheck: None,
+            restart: true,
+        });
+    }
+
+    specs
+}
+
+/// The validator client, started with `args`, or its run script if it has one
+fn validator_client_spec(node_dir: &str, env: &CharonEnv, args: Vec<String>) -> ServiceSpec {
+    let client = env.validator_client;
+
+    let mut binds = vec![
+        format!("{node_dir}/.charon/validator_keys:{KEYS_DIR}"),
+        format!("{node_dir}/data/{}:{DATA_DIR}", client.name()),
+    ];
+    let entrypoint = client.run_script().map(|script| {
+        binds.push(format!("{node_dir}/{script}:/opt/validator/run.sh"));
+        vec![
+            String::from("/bin/sh"),
+            String::from("/opt/validator/run.sh"),
+        ]
+    });
+
+    ServiceSpec {
+        service: VALIDATOR_CLIENT,
+        image: client.image().to_string(),
+        entrypoint,
+        cmd: Some(args),
+        env: vec![
+            format!("NETWORK={}", env.network.name()),
+            format!("BEACON_NODE_ADDRESS={BEACON_NODE_ADDRESS}"),
+            format!("BUILDER_API_ENABLED={}", env.builder_api()),
+        ],
+        binds,
+        ports: Vec::new(),
+        healthcheck: None,
+        restart: true,
+    }
+}
+
+/// Create the Prometheus data dir, owned by the user the image runs as
+///
+/// Data dirs written by Prometheus running as root are handed over as a whole.
+fn create_prometheus_data_dir(node_dir: &Path) -> Result<()> {
+    fn chown_all(path: &Path) -> std::io::Result<()> {
+        std::os::unix::fs::lchown(path, Some(PROMETHEUS_UID), Some(PROMETHEUS_UID))?;
+        if std::fs::symlink_metadata(path)?.is_dir() {
+            for entry in std::fs::read_dir(path)? {
+                chown_all(&entry?.path())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    let path = node_dir.join("data").join("prometheus");
+    std::fs::create_dir_all(&path)?;
+    if std::fs::metadata(&path)?.uid() != PROMETHEUS_UID {
+        chown_all(&path).map_err(|e| {
+            eyre!(
+                "Failed to hand {} to the Prometheus user {PROMETHEUS_UID}: {e}",
+                path.display()
+            )
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Import the key shares for validator clients that don't do so in their run script
+async fn import_validator_keys(
+    docker: &Docker,
+    stack: &str,
+    node_dir: &Path,
+    env: &CharonEnv,
+) -> Result<()> {
+    let client = env.validator_client;
+    client.prepare(&node_dir.join("data").join(client.name()))?;
+
+    let mut keystores = Vec::new();
+    let mut entries = tokio::fs::read_dir(node_dir.join(".charon").join("validator_keys")).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        if file_name.starts_with("keystore-") && file_name.ends_with(".json") {
+            keystores.push(file_name);
+        }
+    }
+    keystores.sort();
+
+    let node_dir = node_dir.display().to_string();
+    for args in client.import_args(&env.network, &keystores) {
+        let spec = ServiceSpec {
+            service: VALIDATOR_CLIENT_IMPORT,
+            restart: false,
+            ..validator_client_spec(&node_dir, env, args)
+        };
+        run_one_off(docker, stack, spec).await?;
+    }
+
+    Ok(())
+}
+
+/// Create a container, run it to completion and remove it
+async fn run_one_off(docker: &Docker, stack: &str, spec: ServiceSpec) -> Result<()> {
+    pull_image(docker, &spec.image).await?;
+    let container = create_container(docker, stack, spec).await?;
+    let result = run_to_completion(docker, &container).await;
+    docker
+        .remove_container(
+            &container.id,
+            Some(RemoveContainerOptions {
+                force: true,
+                ..Default::default()
+            }),
+        )
+        .await?;
+
+    result
+}
+
+async fn run_to_completion(docker: &Docker, container: &StackContainer) -> Result<()> {
+    docker
+        .start_container::<String>(&container.id, None)
+        .await
+        .map_err(|e| eyre!("Failed to start {}: {e}", container.service))?;
+
+    // Non-zero exit codes are reported as errors, they're checked below with the logs
+    let _ = docker
+        .wait_container::<String>(&container.id, None)
+        .collect::<Vec<_>>()
+        .await;
+
+    let exit_code = docker
+        .inspect_container(&container.id, None)
+        .await?
+        .state
+        .and_then(|state| state.exit_code);
+    if exit_code != Some(0) {
+        let logs = container_logs(docker, &container.id, FAILURE_LOG_LINES).await;
+        bail!(
+            "{} exited with code {exit_code:?}:\n{logs}",
+            container.service
+        );
+    }
+
+    Ok(())
+}
+
+async fn ensure_network(docker: &Docker, name: &str) -> Result<()> {
+    match docker
+        .inspect_network(name, None::<InspectNetworkOptions<String>>)
+        .await
+    {
+        Ok(_) => return Ok(()),
+        Err(DockerError::DockerResponseServerError {
+            status_code: 404, ..
+        }) => {}
+        Err(e) => return Err(eyre!("Failed to inspect network {name}: {e}")),
+    }
+
+    tracing::info!("Creating network {name}");
+    docker
+        .create_network(CreateNetworkOptions {
+            name,
+            driver: "bridge",
+            labels: HashMap::from([(STACK_LABEL, name)]),
+            ..Default::default()
+        })
+        .await
+        .map_err(|e| eyre!("Failed to create network {name}: {e}"))?;
+
+    Ok(())
+}
+
+async fn pull_image(docker: &Docker, image: &str) -> Result<()> {
+    tracing::debug!("Pulling {image}");
+    docker
+        .create_image(
+            Some(CreateImageOptions {
+                from_image: image,
+                ..Default::default()
+            }),
+            None,
+            None,
+        )
+        .try_collect::<Vec<_>>()
+        .await
+        .map_err(|e| eyre!("Failed to pull {image}: {e}"))?;
+
+    Ok(())
+}
+
+async fn create_container(
+    docker: &Docker,
+    stack: &str,
+    spec: ServiceSpec,
+) -> Result<StackContainer> {
+    let name = format!("{stack}-{}", spec.service);
+
+    let ports = spec
+        .ports
+        .iter()
+        .map(|port| format!("{port}/tcp"))
+        .collect::<Vec<_>>();
+    let port_bindings = ports
+        .iter()
+        .zip(&spec.ports)
+        .map(|(port, host_port)| {
+            let binding = PortBinding {
+                host_ip: None,
+                host_port: Some(host_port.to_string()),
+            };
+            (port.clone(), Some(vec![binding]))
+        })
+        .collect();
+
+    let config = Config {
+        image: Some(spec.image),
+        entrypoint: spec.entrypoint,
+        cmd: spec.cmd,
+        env: Some(spec.env),
+        labels: Some(HashMap::from([(
+            STACK_LABEL.to_string(),
+            stack.to_string(),
+        )])),
+        exposed_ports: Some(
+            ports
+                .into_iter()
+                .map(|port| (port, HashMap::new()))
+                .collect(),
+        ),
+        healthcheck: spec.healthcheck,
+        host_config: Some(HostConfig {
+            binds: Some(spec.binds),
+            port_bindings: Some(port_bindings),
+            restart_policy: Some(RestartPolicy {
+                name: Some(if spec.restart {
+                    RestartPolicyNameEnum::UNLESS_STOPPED
+                } else {
+                    RestartPolicyNameEnum::NO
+                }),
+                maximum_retry_count: None,
+            }),
+            network_mode: Some(stack.to_string()),
+            ..Default::default()
+        }),
+        networking_config: Some(NetworkingConfig {
+            endpoints_config: HashMap::from([(
+                stack.to_string(),
+                EndpointSettings {
+                    aliases: Some(vec![spec.service.to_string()]),
+                    ..Default::default()
+                },
+            )]),
+        }),
+        ..Default::default()
+    };
+
+    let response = docker
+        .create_container(
+            Some(CreateContainerOptions {
+                name: name.as_str(),
+                platform: None,
+            }),
+            config,
+        )
+        .await
+        .map_err(|e| eyre!("Failed to create {name}: {e}"))?;
+    for warning in response.warnings {
+        tracing::warn!("{name}: {warning}");
+    }
+
+    Ok(StackContainer {
+        service: spec.service,
+        name,
+        id: response.id,
+    })
+}
+
+/// Collect the last `lines` lines of a container's output
+pub(crate) async fn container_logs(docker: &Docker, id: &str, lines: usize) -> String {
+    docker
+        .logs(
+            id,
+            Some(LogsOptions::<String> {
+                stdout: true,
+                stderr: true,
+                tail: lines.to_string(),
+                ..Default::default()
+            }),
+        )
+        .filter_map(|out| async move { out.ok() })
+        .map(|out| String::from_utf8_lossy(out.as_ref()).into_owned())
+        .collect::<Vec<_>>()
+        .await
+        .concat()
+}
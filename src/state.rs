@@ -0,0 +1,71 @@
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The file the [`ClusterState`] is persisted to, in the data dir next to the charon node
+pub(crate) const STATE_FILE: &str = "cluster-state.json";
+
+/// How far an operator has come in creating and running its cluster
+///
+/// The states are ordered, an operator only ever moves forward through them, see
+/// [`Operator::advance_state`](crate::Operator::advance_state). Every transition is persisted, so
+/// a restarted operator keeps its progress. Only a running or exiting validator is restarted on
+/// its own, an operator that was still creating the cluster waits for the next `create_cluster`
+/// call, which reuses its ENR and, if it still matches, its cluster definition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ClusterState {
+    /// No ENR has been created yet
+    NeedsEnr,
+    /// The ENR is created, the other operators' ENRs are yet to arrive
+    ExchangingEnrs,
+    /// Every operator's ENR has arrived, the DKG config is yet to be created or to arrive
+    AwaitingDefinition,
+    /// The DKG config is in place, the ceremony is yet to succeed on every operator
+    DkgPending,
+    /// Every operator produced the same cluster lock
+    DkgDone,
+    /// The validator has been started
+    ValidatorRunning,
+    /// The exits of all the cluster's validators are broadcast, they keep their duties until their
+    /// exit epoch
+    Exiting,
+    /// The beacon chain reports all the cluster's validators as exited, the node can be torn down
+    Exited,
+}
+
+impl ClusterState {
+    /// Read the persisted state
+    ///
+    /// Data dirs from before the state was persisted have none, in which case it is worked out
+    /// from the files in the charon node.
+    pub fn load(state_path: &Path, node_path: &Path) -> Result<Self> {
+        if state_path.exists() {
+            let content = std::fs::read_to_string(state_path)?;
+            return Ok(serde_json::from_str(&content)?);
+        }
+
+        let charon_dir = node_path.join(".charon");
+        let state = if charon_dir.join("cluster-lock.json").exists() {
+            ClusterState::DkgDone
+        } else if charon_dir.join("cluster-definition.json").exists() {
+            ClusterState::DkgPending
+        } else if charon_dir.join("charon-enr-private-key").exists() {
+            ClusterState::ExchangingEnrs
+        } else {
+            ClusterState::NeedsEnr
+        };
+
+        tracing::info!("No cluster state found, detected {state:?}");
+        Ok(state)
+    }
+
+    /// Persist the state, replacing the file at once so a crash can't leave it half written
+    pub async fn save(self, state_path: &Path) -> Result<()> {
+        let tmp_path = state_path.with_extension("json.tmp");
+        tokio::fs::write(&tmp_path, serde_json::to_string_pretty(&self)?).await?;
+        tokio::fs::rename(&tmp_path, state_path).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,323 @@
+use crate::Network;
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use gadget_sdk as sdk;
+use sdk::random::rand::rngs::OsRng;
+use sdk::random::RngCore;
+use std::fmt;
+use std::path::Path;
+
+/// Where the cluster's `.charon/validator_keys` is mounted in the validator client container
+pub const KEYS_DIR: &str = "/opt/charon/keys";
+/// Where the client's own data dir, `<node dir>/data/<client>`, is mounted
+pub const DATA_DIR: &str = "/opt/data";
+/// Charon's beacon API, which validator clients connect to instead of a beacon node
+pub const BEACON_NODE_ADDRESS: &str = "http://charon:3600";
+/// The port every validator client serves its metrics on
+pub const METRICS_PORT: u16 = 5064;
+/// The EIP-3076 slashing protection interchange file, in [`DATA_DIR`]
+pub const INTERCHANGE_FILE: &str = "slashing_protection.json";
+
+/// The validator clients an operator can choose from, see [`validator_client`]
+pub const VALIDATOR_CLIENTS: &[&dyn ValidatorClient] =
+    &[&Lighthouse, &Teku, &Nimbus, &Lodestar, &Prysm];
+
+/// A validator client, running with the key shares of the cluster
+///
+/// The container gets [`KEYS_DIR`] and [`DATA_DIR`] mounted, and `NETWORK`, `BEACON_NODE_ADDRESS`
+/// and `BUILDER_API_ENABLED` set in its environment. Operators of a cluster can each run a different
+/// client.
+pub trait ValidatorClient: fmt::Debug + Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn image(&self) -> &'static str;
+
+    /// The script that imports the keys and starts the client, relative to the node dir
+    ///
+    /// Clients without one are started with [`ValidatorClient::args`] instead.
+    fn run_script(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// The arguments to the image's entrypoint, for clients without a run script
+    ///
+    /// `builder` is whether blocks are proposed through the builder API.
+    fn args(&self, _network: &Network, _builder: bool) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// The arguments to the image's entrypoint to import each of `keystores` before starting
+    fn import_args(&self, _network: &Network, _keystores: &[String]) -> Vec<Vec<String>> {
+        Vec::new()
+    }
+
+    /// Set up the client's data dir on the host before it starts
+    fn prepare(&self, _data_dir: &Path) -> Result<()> {
+        Ok(())
+    }
+
+    /// The arguments to the image's entrypoint to export the slashing protection DB to, or import
+    /// it from, [`INTERCHANGE_FILE`]
+    ///
+    /// `None` if the client can't.
+    fn slashing_protection_args(
+        &self,
+        _network: &Network,
+        _interchange: Interchange,
+    ) -> Option<Vec<String>> {
+        None
+    }
+}
+
+/// Which way slashing protection data moves through an [`INTERCHANGE_FILE`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interchange {
+    Export,
+    Import,
+}
+
+impl Interchange {
+    fn command(self) -> &'static str {
+        match self {
+            Interchange::Export => "export",
+            Interchange::Import => "import",
+        }
+    }
+}
+
+/// Find a validator client by name
+pub fn validator_client(name: &str) -> Result<&'static dyn ValidatorClient> {
+    VALIDATOR_CLIENTS
+        .iter()
+        .copied()
+        .find(|client| client.name() == name)
+        .ok_or_else(|| {
+            let names = VALIDATOR_CLIENTS
+                .iter()
+                .map(|client| client.name())
+                .collect::<Vec<_>>();
+            eyre!(
+                "Unsupported validator client `{name}`, expected one of {}",
+                names.join(", ")
+            )
+        })
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Lighthouse;
+
+impl ValidatorClient for Lighthouse {
+    fn name(&self) -> &'static str {
+        "lighthouse"
+    }
+
+    fn image(&self) -> &'static str {
+        "sigp/lighthouse:v5.3.0"
+    }
+
+    fn run_script(&self) -> Option<&'static str> {
+        Some("lighthouse/run.sh")
+    }
+
+    fn slashing_protection_args(
+        &self,
+        network: &Network,
+        interchange: Interchange,
+    ) -> Option<Vec<String>> {
+        Some(vec![
+            String::from("lighthouse"),
+            format!("--network={}", network.name()),
+            String::from("account"),
+            String::from("validator"),
+            String::from("slashing-protection"),
+            interchange.command().to_string(),
+            format!("{DATA_DIR}/{INTERCHANGE_FILE}"),
+            format!("--datadir={DATA_DIR}"),
+        ])
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Teku;
+
+impl ValidatorClient for Teku {
+    fn name(&self) -> &'static str {
+        "teku"
+    }
+
+    fn image(&self) -> &'static str {
+        "consensys/teku:24.10.3"
+    }
+
+    fn run_script(&self) -> Option<&'static str> {
+        Some("teku/run.sh")
+    }
+
+    fn slashing_protection_args(
+        &self,
+        _network: &Network,
+        interchange: Interchange,
+    ) -> Option<Vec<String>> {
+        let file = match interchange {
+            Interchange::Export => format!("--to={DATA_DIR}/{INTERCHANGE_FILE}"),
+            Interchange::Import => format!("--from={DATA_DIR}/{INTERCHANGE_FILE}"),
+        };
+
+        Some(vec![
+            String::from("slashing-protection"),
+            interchange.command().to_string(),
+            format!("--data-path={DATA_DIR}"),
+            file,
+        ])
+    }
+}
+
+/// Nimbus's validator client image doesn't ship its slashing protection tooling, so it can't
+/// export or import interchange files
+#[derive(Debug, Clone, Copy)]
+pub struct Nimbus;
+
+impl ValidatorClient for Nimbus {
+    fn name(&self) -> &'static str {
+        "nimbus"
+    }
+
+    fn image(&self) -> &'static str {
+        "statusim/nimbus-validator-client:multiarch-v24.10.0"
+    }
+
+    fn run_script(&self) -> Option<&'static str> {
+        Some("nimbus/run.sh")
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Lodestar;
+
+impl ValidatorClient for Lodestar {
+    fn name(&self) -> &'static str {
+        "lodestar"
+    }
+
+    fn image(&self) -> &'static str {
+        "chainsafe/lodestar:v1.23.0"
+    }
+
+    fn run_script(&self) -> Option<&'static str> {
+        Some("lodestar/run.sh")
+    }
+
+    fn slashing_protection_args(
+        &self,
+        network: &Network,
+        interchange: Interchange,
+    ) -> Option<Vec<String>> {
+        Some(vec![
+            String::from("validator"),
+            String::from("slashing-protection"),
+            interchange.command().to_string(),
+            format!("--file={DATA_DIR}/{INTERCHANGE_FILE}"),
+            format!("--dataDir={DATA_DIR}"),
+            format!("--network={}", network.name()),
+        ])
+    }
+}
+
+/// Prysm's image has no shell, so the keys are imported by running it once per keystore
+#[derive(Debug, Clone, Copy)]
+pub struct Prysm;
+
+impl Prysm {
+    const WALLET_DIR: &'static str = "/opt/data/wallet";
+    const WALLET_PASSWORD_FILE: &'static str = "wallet-password.txt";
+
+    fn common_args(network: &Network) -> Vec<String> {
+        vec![
+            String::from("--accept-terms-of-use"),
+            format!("--{}", network.name()),
+            format!("--wallet-dir={}", Self::WALLET_DIR),
+            format!(
+                "--wallet-password-file={DATA_DIR}/{}",
+                Self::WALLET_PASSWORD_FILE
+            ),
+        ]
+    }
+}
+
+impl ValidatorClient for Prysm {
+    fn name(&self) -> &'static str {
+        "prysm"
+    }
+
+    fn image(&self) -> &'static str {
+        "gcr.io/prysmaticlabs/prysm/validator:v5.3.0"
+    }
+
+    fn args(&self, network: &Network, builder: bool) -> Vec<String> {
+        let mut args = Self::common_args(network);
+        args.extend([
+            format!("--datadir={DATA_DIR}"),
+            format!("--beacon-rest-api-provider={BEACON_NODE_ADDRESS}"),
+            String::from("--enable-beacon-rest-api"),
+            String::from("--distributed"),
+            String::from("--monitoring-host=0.0.0.0"),
+            format!("--monitoring-port={METRICS_PORT}"),
+        ]);
+        if builder {
+            args.push(String::from("--enable-builder"));
+        }
+        args
+    }
+
+    fn import_args(&self, network: &Network, keystores: &[String]) -> Vec<Vec<String>> {
+        keystores
+            .iter()
+            .map(|keystore| {
+                let password = keystore.trim_end_matches(".json");
+                let mut args = vec![String::from("accounts"), String::from("import")];
+                args.extend(Self::common_args(network));
+                args.extend([
+                    format!("--keys-dir={KEYS_DIR}/{keystore}"),
+                    format!("--account-password-file={KEYS_DIR}/{password}.txt"),
+                ]);
+                args
+            })
+            .collect()
+    }
+
+    fn prepare(&self, data_dir: &Path) -> Result<()> {
+        let password_path = data_dir.join(Self::WALLET_PASSWORD_FILE);
+        if password_path.exists() {
+            return Ok(());
+        }
+
+        let mut password = [0u8; 32];
+        OsRng.fill_bytes(&mut password);
+
+        std::fs::create_dir_all(data_dir)?;
+        std::fs::write(password_path, hex::encode(password))?;
+        Ok(())
+    }
+
+    /// Prysm exports to a directory, always naming the file [`INTERCHANGE_FILE`]
+    fn slashing_protection_args(
+        &self,
+        _network: &Network,
+        interchange: Interchange,
+    ) -> Option<Vec<String>> {
+        let file = match interchange {
+            Interchange::Export => format!("--slashing-protection-export-dir={DATA_DIR}"),
+            Interchange::Import => {
+                format!("--slashing-protection-json-file={DATA_DIR}/{INTERCHANGE_FILE}")
+            }
+        };
+
+        Some(vec![
+            String::from("slashing-protection-history"),
+            interchange.command().to_string(),
+            String::from("--accept-terms-of-use"),
+            format!("--datadir={DATA_DIR}"),
+            file,
+        ])
+    }
+}